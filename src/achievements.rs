@@ -0,0 +1,89 @@
+// 打到一定程度时弹出的成就，每局每个只拿一次
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Achievement {
+    Streak10,
+    Streak25,
+    Sharpshooter, // 打中够多只而且几乎没打空
+    PerfectWave,  // 无尽模式下一整波既没打空也没放跑
+}
+
+// 神枪手要求的命中次数和命中率
+const SHARPSHOOTER_HITS: u32 = 20;
+const SHARPSHOOTER_ACCURACY: f64 = 0.9;
+
+impl Achievement {
+    pub fn banner(&self) -> &'static str {
+        match self {
+            Achievement::Streak10 => "10-hit streak!",
+            Achievement::Streak25 => "25-hit streak!",
+            Achievement::Sharpshooter => "Sharpshooter!",
+            Achievement::PerfectWave => "Perfect wave!",
+        }
+    }
+}
+
+// 本局已经拿到的成就
+#[derive(Debug, Default)]
+pub struct Achievements {
+    unlocked: Vec<Achievement>,
+}
+
+impl Achievements {
+    // 打中之后按连击数和命中率检查，返回这一下新拿到的成就
+    pub fn on_hit(&mut self, combo: u32, hits: u32, misses: u32) -> Option<Achievement> {
+        let accuracy = f64::from(hits) / f64::from(hits + misses);
+        let achievement = match combo {
+            25 => Achievement::Streak25,
+            10 => Achievement::Streak10,
+            _ if hits >= SHARPSHOOTER_HITS && accuracy >= SHARPSHOOTER_ACCURACY => {
+                Achievement::Sharpshooter
+            }
+            _ => return None,
+        };
+        self.unlock(achievement)
+    }
+
+    // 一波结束时检查这一波有没有失误
+    pub fn on_wave(&mut self, clean: bool) -> Option<Achievement> {
+        if !clean {
+            return None;
+        }
+        self.unlock(Achievement::PerfectWave)
+    }
+
+    pub fn reset(&mut self) {
+        self.unlocked.clear();
+    }
+
+    fn unlock(&mut self, achievement: Achievement) -> Option<Achievement> {
+        if self.unlocked.contains(&achievement) {
+            return None;
+        }
+        self.unlocked.push(achievement);
+        Some(achievement)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn each_achievement_fires_once_per_game() {
+        let mut achievements = Achievements::default();
+        assert_eq!(achievements.on_hit(9, 9, 5), None);
+        assert_eq!(achievements.on_hit(10, 10, 5), Some(Achievement::Streak10));
+        assert_eq!(achievements.on_hit(10, 12, 5), None);
+        assert_eq!(achievements.on_hit(3, 20, 5), None);
+        assert_eq!(
+            achievements.on_hit(3, 45, 5),
+            Some(Achievement::Sharpshooter)
+        );
+        assert_eq!(achievements.on_hit(4, 46, 5), None);
+        assert_eq!(achievements.on_wave(false), None);
+        assert_eq!(achievements.on_wave(true), Some(Achievement::PerfectWave));
+        assert_eq!(achievements.on_wave(true), None);
+        achievements.reset();
+        assert_eq!(achievements.on_hit(10, 10, 0), Some(Achievement::Streak10));
+    }
+}
@@ -0,0 +1,80 @@
+use drawille::Canvas;
+use unicode_width::{UnicodeWidthChar, UnicodeWidthStr};
+
+// 把横幅文字画在和画面一样大的画布上，居中摆放，返回不超过 rows 行、每行不超过 columns 列的内容
+pub fn render(text: &str, columns: usize, rows: usize) -> Vec<String> {
+    let lines = wrap(text, columns);
+    let top = rows.saturating_sub(lines.len()) / 2;
+    let mut canvas = Canvas::new(columns as u32 * 2, rows as u32 * 4);
+    for (i, line) in lines.iter().take(rows).enumerate() {
+        let left = columns.saturating_sub(line.width()) / 2;
+        canvas.text(
+            left as u32 * 2,
+            (top + i) as u32 * 4,
+            columns as u32 * 2,
+            line,
+        );
+    }
+    // 画布按格子数排版，宽字符会把一行撑宽，多出来的都是末尾的空格
+    canvas
+        .rows()
+        .into_iter()
+        .take(rows)
+        .map(|row| clip(&row, columns))
+        .collect()
+}
+
+fn clip(row: &str, columns: usize) -> String {
+    let mut width = 0;
+    row.chars()
+        .take_while(|ch| {
+            width += ch.width().unwrap_or(0);
+            width <= columns
+        })
+        .collect()
+}
+
+// 按显示宽度折行，尽量在空格处断开；中文之间没有空格，放不下时就地断开
+fn wrap(text: &str, columns: usize) -> Vec<String> {
+    let mut lines = vec![];
+    let mut line = String::new();
+    let mut width = 0;
+    for ch in text.chars() {
+        let ch_width = ch.width().unwrap_or(0);
+        if ch_width > columns {
+            continue;
+        }
+        if width + ch_width > columns {
+            let rest = match line.rfind(' ') {
+                Some(at) if ch != ' ' => line.split_off(at + 1),
+                _ => String::new(),
+            };
+            lines.push(line.trim_end().to_string());
+            line = rest;
+            width = line.width();
+            if ch == ' ' {
+                continue;
+            }
+        }
+        line.push(ch);
+        width += ch_width;
+    }
+    if !line.is_empty() {
+        lines.push(line);
+    }
+    lines
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn text_is_wrapped_and_clipped_to_the_screen() {
+        let rows = render("1024 cheers! 恭喜你过关啦！！", 10, 3);
+        assert_eq!(rows.len(), 3);
+        assert!(rows.iter().all(|row| row.width() <= 10));
+        assert_eq!(rows[0].trim(), "1024");
+        assert_eq!(rows[1].trim(), "cheers!");
+    }
+}
@@ -0,0 +1,60 @@
+use std::{hint::black_box, time::Instant};
+
+use crate::{
+    layout::{Layout, DEFAULT_GAP},
+    Dimension, GameView, DIVIDER, THEMES,
+};
+
+const FRAMES: u32 = 1000;
+
+// 不碰终端，只计时把整个画面渲染成字符串要多久
+pub fn run() {
+    let sizes = [
+        Dimension {
+            width: 70,
+            height: 25,
+        },
+        Dimension {
+            width: 140,
+            height: 50,
+        },
+        Dimension {
+            width: 280,
+            height: 100,
+        },
+    ];
+    for theme in [THEMES[0], THEMES[1]] {
+        for size in &sizes {
+            let mut view = board(size);
+            view.theme = theme;
+            let started_at = Instant::now();
+            for _ in 0..FRAMES {
+                black_box(view.render_to_string());
+            }
+            let per_frame = started_at.elapsed() / FRAMES;
+            println!(
+                "{}x{} {}: {}us per frame",
+                size.width,
+                size.height,
+                theme.name,
+                per_frame.as_micros()
+            );
+        }
+    }
+}
+
+// 默认棋盘，所有洞都出地鼠
+fn board(size: &Dimension) -> GameView {
+    let mut view = GameView::new(size);
+    view.build_block(0, size.height - 1, 0, size.width - 1)
+        .unwrap();
+    view.build_block(0, size.height - 1, 0, DIVIDER).unwrap();
+    for hole in Layout::grid(DEFAULT_GAP).unwrap().holes {
+        view.build_block(hole.top, hole.bottom, hole.left, hole.right)
+            .unwrap();
+        view.add_hole(&hole);
+    }
+    let holes: Vec<usize> = (0..view.holes.len()).collect();
+    view.spawn(&holes);
+    view
+}
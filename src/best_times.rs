@@ -0,0 +1,112 @@
+use std::{collections::BTreeMap, fs, io, path::Path, time::Duration};
+
+// 每个洞历来最快的反应时间，跨局保存，从 0 开始数洞
+#[derive(Debug, Default, PartialEq)]
+pub struct BestTimes {
+    best: BTreeMap<usize, Duration>,
+}
+
+impl BestTimes {
+    // 文件还不存在时当作没有纪录，第一局结束时再创建
+    pub fn load(path: &Path) -> Result<Self, String> {
+        match fs::read_to_string(path) {
+            Ok(text) => {
+                BestTimes::parse(&text).map_err(|err| format!("{}: {}", path.display(), err))
+            }
+            Err(err) if err.kind() == io::ErrorKind::NotFound => Ok(BestTimes::default()),
+            Err(err) => Err(format!(
+                "Can not read the best times {}: {}",
+                path.display(),
+                err
+            )),
+        }
+    }
+
+    // 每行一个洞：洞号 毫秒，洞号和数字键一样从 1 开始，同一个洞写了两次说明文件被改坏了
+    pub fn parse(text: &str) -> Result<Self, String> {
+        let mut best = BTreeMap::new();
+        for (i, line) in text.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() {
+                continue;
+            }
+            let fields = line
+                .split_whitespace()
+                .map(|field| field.parse::<u64>())
+                .collect::<Result<Vec<_>, _>>();
+            match fields.as_deref() {
+                Ok(&[hole, millis]) if hole >= 1 => {
+                    let time = Duration::from_millis(millis);
+                    if best.insert(hole as usize - 1, time).is_some() {
+                        return Err(format!("line {}: hole {} is listed twice", i + 1, hole));
+                    }
+                }
+                _ => {
+                    return Err(format!(
+                        "line {}: expected a hole number and milliseconds, got {:?}",
+                        i + 1,
+                        line
+                    ))
+                }
+            }
+        }
+        Ok(BestTimes { best })
+    }
+
+    pub fn save(&self, path: &Path) -> io::Result<()> {
+        let text: String = self
+            .best
+            .iter()
+            .map(|(idx, time)| format!("{} {}\n", idx + 1, time.as_millis()))
+            .collect();
+        fs::write(path, text)
+    }
+
+    pub fn get(&self, idx: usize) -> Option<Duration> {
+        self.best.get(&idx).copied()
+    }
+
+    // 并入这一局各洞最快的一次，按毫秒比较，返回破了纪录的洞
+    pub fn merge(&mut self, game: &[Option<Duration>]) -> Vec<usize> {
+        let mut improved = vec![];
+        for (idx, time) in game.iter().enumerate() {
+            let Some(time) = time else {
+                continue;
+            };
+            let time = Duration::from_millis(time.as_millis() as u64);
+            if self.get(idx).is_none_or(|best| time < best) {
+                self.best.insert(idx, time);
+                improved.push(idx);
+            }
+        }
+        improved
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn faster_hits_replace_the_record() {
+        let mut best = BestTimes::parse("1 300\n\n3 250\n").unwrap();
+        let ms = Duration::from_millis;
+        let improved = best.merge(&[Some(ms(310)), Some(ms(400)), Some(ms(249)), None]);
+        assert_eq!(improved, vec![1, 2]);
+        assert_eq!(best.get(0), Some(ms(300)));
+        assert_eq!(best.get(2), Some(ms(249)));
+        assert_eq!(best.get(3), None);
+        assert!(BestTimes::parse("0 100").is_err());
+        assert!(BestTimes::parse("2 fast").is_err());
+    }
+
+    #[test]
+    fn malformed_and_duplicate_lines_are_rejected() {
+        let err = |text| BestTimes::parse(text).unwrap_err();
+        assert_eq!(
+            err("1 300\n2 300 7\n"),
+            "line 2: expected a hole number and milliseconds, got \"2 300 7\""
+        );
+        assert_eq!(err("1 300\n\n1 250\n"), "line 3: hole 1 is listed twice");
+    }
+}
@@ -0,0 +1,59 @@
+use drawille::Canvas;
+
+// 3x5 的数字点阵，每行的低三位从左到右对应三个点
+const DIGITS: [[u8; 5]; 10] = [
+    [0b111, 0b101, 0b101, 0b101, 0b111],
+    [0b010, 0b110, 0b010, 0b010, 0b111],
+    [0b111, 0b001, 0b111, 0b100, 0b111],
+    [0b111, 0b001, 0b111, 0b001, 0b111],
+    [0b101, 0b101, 0b111, 0b001, 0b001],
+    [0b111, 0b100, 0b111, 0b001, 0b111],
+    [0b111, 0b100, 0b111, 0b101, 0b111],
+    [0b111, 0b001, 0b001, 0b001, 0b001],
+    [0b111, 0b101, 0b111, 0b101, 0b111],
+    [0b111, 0b101, 0b111, 0b001, 0b111],
+];
+// 字与字之间空一列点
+const GAP: u32 = 1;
+
+// 一个盲文字符高四个点，五个点高的数字要占两行
+pub const BIG_ROWS: usize = 2;
+
+// 用盲文点阵把数字和小数点画大，其他字符当作空格
+pub fn render(text: &str) -> Vec<String> {
+    let mut canvas = Canvas::new(0, 0);
+    let mut x = 0;
+    for ch in text.chars() {
+        if let Some(digit) = ch.to_digit(10) {
+            for (y, bits) in DIGITS[digit as usize].iter().enumerate() {
+                for dx in 0..3 {
+                    if bits >> (2 - dx) & 1 == 1 {
+                        canvas.set(x + dx, y as u32);
+                    }
+                }
+            }
+            x += 3 + GAP;
+        } else if ch == '.' {
+            canvas.set(x, 4);
+            x += 1 + GAP;
+        } else {
+            x += 3 + GAP;
+        }
+    }
+    let mut rows = canvas.rows();
+    rows.resize(BIG_ROWS, String::new());
+    rows
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn digits_take_two_cells_and_two_rows() {
+        let rows = render("10");
+        assert_eq!(rows.len(), BIG_ROWS);
+        assert_eq!(rows[0].trim_end().chars().count(), 4);
+        assert_eq!(rows, vec!["⢺ ⡏⡇", "⠉⠁⠉⠁"]);
+    }
+}
@@ -0,0 +1,220 @@
+use std::{env, str::FromStr};
+use unicode_width::UnicodeWidthStr;
+
+use crate::{theme::ThemeGlyphs, CHAR_VIEW_LIST, HOLE_WIDTH};
+
+// 启动时根据环境变量推测终端支持什么，推测不准时可以用参数强制指定
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Capabilities {
+    pub unicode: bool,    // 制表符和盲文点阵
+    pub emoji: bool,      // 地鼠和打中标记
+    pub color: bool,      // 主题颜色
+    pub alt_screen: bool, // 备用屏幕，退出后恢复原来的终端内容
+}
+
+impl Capabilities {
+    pub fn detect() -> Self {
+        Capabilities::detect_from(|name| env::var(name).ok())
+    }
+
+    pub fn detect_from<F: Fn(&str) -> Option<String>>(var: F) -> Self {
+        let term = var("TERM").unwrap_or_default();
+        let dumb = term.is_empty() || term == "dumb";
+        // Linux 控制台没有 emoji 字体，也不支持备用屏幕
+        let console = term == "linux";
+        let locale = locale(&var);
+        let unicode = locale.contains("utf-8") || locale.contains("utf8");
+        Capabilities {
+            unicode,
+            emoji: unicode && !console,
+            color: !dumb && var("NO_COLOR").is_none(),
+            alt_screen: !dumb && !console,
+        }
+    }
+}
+
+// 和 setlocale 一样，LC_ALL 优先于 LC_CTYPE，再到 LANG，统一转成小写
+pub fn locale<F: Fn(&str) -> Option<String>>(var: &F) -> String {
+    ["LC_ALL", "LC_CTYPE", "LANG"]
+        .iter()
+        .filter_map(|&name| var(name))
+        .find(|value| !value.is_empty())
+        .unwrap_or_default()
+        .to_lowercase()
+}
+
+// 随终端能力变化的字符
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Glyphs {
+    pub frame: [char; 16],
+    pub mole: &'static str,
+    pub hit: &'static str,
+    pub squish: [&'static str; 3],
+    pub escaped: &'static str, // 地鼠没被打中就溜走了
+    pub shades: [char; 4],     // 热力图从浅到深
+    pub map: [char; 2],        // 小地图上有地鼠和没有地鼠的洞
+    pub empty: &'static str,   // 空洞
+    pub ghost: &'static str,   // 最好那一局这时有地鼠的空洞，暗色显示
+}
+
+const ASCII_FRAME: [char; 16] = [
+    ' ', '|', '|', '|', '-', '+', '+', '+', '-', '+', '+', '+', '-', '+', '+', '+',
+];
+// 和 CHAR_VIEW_LIST 的下标一一对应，只有一头的线段画成直线
+const SINGLE_FRAME: [char; 16] = [
+    ' ', '│', '│', '│', '─', '└', '┌', '├', '─', '┘', '┐', '┤', '─', '┴', '┬', '┼',
+];
+const ROUNDED_FRAME: [char; 16] = [
+    ' ', '│', '│', '│', '─', '╰', '╭', '├', '─', '╯', '╮', '┤', '─', '┴', '┬', '┼',
+];
+
+// 边框的线型，不指定时按终端能力在双线和 ASCII 之间选
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum BorderStyle {
+    Double,
+    Single,
+    Rounded,
+    Ascii,
+}
+
+impl BorderStyle {
+    pub fn frame(&self) -> [char; 16] {
+        match self {
+            BorderStyle::Double => CHAR_VIEW_LIST,
+            BorderStyle::Single => SINGLE_FRAME,
+            BorderStyle::Rounded => ROUNDED_FRAME,
+            BorderStyle::Ascii => ASCII_FRAME,
+        }
+    }
+}
+
+impl FromStr for BorderStyle {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "double" => Ok(BorderStyle::Double),
+            "single" => Ok(BorderStyle::Single),
+            "rounded" => Ok(BorderStyle::Rounded),
+            "ascii" => Ok(BorderStyle::Ascii),
+            _ => Err(format!(
+                "Unknown border style: {} (expected double, single, rounded or ascii)",
+                s
+            )),
+        }
+    }
+}
+
+pub const EMOJI_GLYPHS: Glyphs = Glyphs {
+    frame: CHAR_VIEW_LIST,
+    mole: "🐭",
+    hit: "❌",
+    squish: ["💥", "❌", "x"],
+    escaped: "💨",
+    shades: ['░', '▒', '▓', '█'],
+    map: ['■', '□'],
+    empty: " ",
+    ghost: "░░",
+};
+
+impl Glyphs {
+    pub fn for_capabilities(caps: &Capabilities) -> Self {
+        let mut glyphs = EMOJI_GLYPHS;
+        if !caps.unicode {
+            glyphs.frame = ASCII_FRAME;
+            glyphs.map = ['#', '.'];
+            glyphs.shades = ['.', ':', '+', '#'];
+            glyphs.ghost = "..";
+        }
+        if !caps.emoji {
+            glyphs.mole = "M";
+            glyphs.hit = "X";
+            glyphs.squish = ["*", "X", "x"];
+            glyphs.escaped = "~";
+        }
+        glyphs
+    }
+
+    // 换上主题自己的字符；放不进洞口的，或者终端不支持 emoji 时不是 ASCII 的，都不换
+    pub fn themed(mut self, theme: &ThemeGlyphs, emoji: bool) -> Self {
+        let fits = |glyph: &&str| glyph.width() <= HOLE_WIDTH && (emoji || glyph.is_ascii());
+        if let Some(mole) = theme.mole.filter(fits) {
+            self.mole = mole;
+        }
+        if let Some(hit) = theme.hit.filter(fits) {
+            self.hit = hit;
+            self.squish[1] = hit;
+        }
+        if let Some(empty) = theme.empty.filter(fits) {
+            self.empty = empty;
+        }
+        self
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use unicode_width::UnicodeWidthChar;
+
+    fn detect(vars: &[(&str, &str)]) -> Capabilities {
+        Capabilities::detect_from(|name| {
+            vars.iter()
+                .find(|(key, _)| *key == name)
+                .map(|(_, value)| value.to_string())
+        })
+    }
+
+    #[test]
+    fn theme_glyphs_must_fit_the_terminal() {
+        let theme = ThemeGlyphs {
+            mole: Some("🦔"),
+            hit: Some("###"),
+            empty: Some("."),
+        };
+        let glyphs = EMOJI_GLYPHS.themed(&theme, true);
+        assert_eq!((glyphs.mole, glyphs.hit, glyphs.empty), ("🦔", "❌", "."));
+        let caps = detect(&[("TERM", "linux"), ("LANG", "en_US.UTF-8")]);
+        let glyphs = Glyphs::for_capabilities(&caps).themed(&theme, caps.emoji);
+        assert_eq!((glyphs.mole, glyphs.hit, glyphs.empty), ("M", "X", "."));
+    }
+
+    #[test]
+    fn border_tables_fill_every_connection() {
+        for style in [
+            BorderStyle::Double,
+            BorderStyle::Single,
+            BorderStyle::Rounded,
+            BorderStyle::Ascii,
+        ] {
+            let frame = style.frame();
+            assert_eq!(frame[0], ' ');
+            // 双线框沿用原来的表，只往一个方向伸出去的那几格一直是汉字占位，这里不查
+            let stubs = [1, 2, 4, 8];
+            assert!((1..16)
+                .filter(|i| style != BorderStyle::Double || !stubs.contains(i))
+                .all(|i| frame[i] != ' ' && frame[i].width() == Some(1)));
+            // 四个角和四个丁字各不相同，ASCII 全都用 + 不算
+            if style == BorderStyle::Ascii {
+                continue;
+            }
+            let mut joints: Vec<char> = [5, 6, 7, 9, 10, 11, 13, 14, 15].map(|i| frame[i]).into();
+            joints.sort_unstable();
+            joints.dedup();
+            assert_eq!(joints.len(), 9, "{:?}", style);
+        }
+    }
+
+    #[test]
+    fn utf8_terminal_gets_everything() {
+        let caps = detect(&[("TERM", "xterm-256color"), ("LANG", "en_US.UTF-8")]);
+        assert!(caps.unicode && caps.emoji && caps.color && caps.alt_screen);
+    }
+
+    #[test]
+    fn dumb_terminal_is_downgraded() {
+        let caps = detect(&[("TERM", "dumb"), ("LC_ALL", "C"), ("LANG", "en_US.UTF-8")]);
+        assert!(!caps.unicode && !caps.emoji && !caps.color && !caps.alt_screen);
+        assert_eq!(Glyphs::for_capabilities(&caps).mole, "M");
+    }
+}
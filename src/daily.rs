@@ -0,0 +1,62 @@
+use std::{
+    fmt,
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+// 每日挑战用的日期，按 UTC 算，各个时区的玩家在同一天拿到同一个种子
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Date {
+    pub year: i64,
+    pub month: u32,
+    pub day: u32,
+}
+
+impl Date {
+    pub fn today() -> Self {
+        let secs = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map_or(0, |elapsed| elapsed.as_secs());
+        Date::from_days((secs / 86_400) as i64)
+    }
+
+    // 1970-01-01 起的天数换成年月日
+    pub fn from_days(days: i64) -> Self {
+        let days = days + 719_468;
+        let era = days.div_euclid(146_097);
+        let day_of_era = days.rem_euclid(146_097);
+        let year_of_era =
+            (day_of_era - day_of_era / 1460 + day_of_era / 36_524 - day_of_era / 146_096) / 365;
+        let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+        let mp = (5 * day_of_year + 2) / 153;
+        let day = (day_of_year - (153 * mp + 2) / 5 + 1) as u32;
+        let month = if mp < 10 { mp + 3 } else { mp - 9 } as u32;
+        let year = year_of_era + era * 400 + i64::from(month <= 2);
+        Date { year, month, day }
+    }
+
+    // 种子就是写成数字的日期，比如 2024-03-09 对应 20240309
+    pub fn seed(&self) -> u64 {
+        (self.year * 10_000 + i64::from(self.month) * 100 + i64::from(self.day)) as u64
+    }
+}
+
+impl fmt::Display for Date {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}-{:02}-{:02}", self.year, self.month, self.day)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn same_date_gives_same_seed() {
+        // 2024-03-09 是 1970-01-01 之后的第 19791 天
+        let date = Date::from_days(19_791);
+        assert_eq!(date.to_string(), "2024-03-09");
+        assert_eq!(date.seed(), 20_240_309);
+        assert_eq!(Date::from_days(19_791).seed(), date.seed());
+        assert_ne!(Date::from_days(19_792).seed(), date.seed());
+    }
+}
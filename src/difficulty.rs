@@ -0,0 +1,123 @@
+use std::{f64::consts::PI, str::FromStr, time::Duration};
+
+use crate::ROUND_TIME;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Difficulty {
+    pub name: &'static str,
+    pub spawn_interval: Duration, // 每轮出地鼠的间隔
+    pub mole_density: f64,        // 每轮最多出几只，按洞数的比例算
+    pub round_time: u8,           // 一局的秒数，无尽模式不用
+    pub points_factor: u128,      // 每只得分的倍数
+}
+
+pub const DEFAULT_DIFFICULTY: &str = "normal";
+
+// 闪电战：一局很短，地鼠又多又快，得分加倍
+pub const BLITZ: &str = "blitz";
+
+pub const DIFFICULTIES: [Difficulty; 4] = [
+    Difficulty {
+        name: "easy",
+        spawn_interval: Duration::from_millis(1500),
+        mole_density: 1.0 / 3.0,
+        round_time: ROUND_TIME,
+        points_factor: 1,
+    },
+    Difficulty {
+        name: "normal",
+        spawn_interval: Duration::from_millis(1000),
+        mole_density: 2.0 / 3.0,
+        round_time: ROUND_TIME,
+        points_factor: 1,
+    },
+    Difficulty {
+        name: "hard",
+        spawn_interval: Duration::from_millis(700),
+        mole_density: 2.0 / 3.0,
+        round_time: ROUND_TIME,
+        points_factor: 1,
+    },
+    Difficulty {
+        name: BLITZ,
+        spawn_interval: Duration::from_millis(450),
+        mole_density: 0.75,
+        round_time: 20,
+        points_factor: 2,
+    },
+];
+
+impl Difficulty {
+    // 有 holes 个洞时每轮最多出几只，至少一只
+    pub fn max_moles(&self, holes: usize) -> usize {
+        ((holes as f64 * self.mole_density).round() as usize).clamp(1, holes.max(1))
+    }
+}
+
+pub fn find(name: &str) -> Option<Difficulty> {
+    DIFFICULTIES.iter().copied().find(|d| d.name == name)
+}
+
+// 一局里出地鼠的疏密变化
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum SpawnCurve {
+    Flat, // 始终一样
+    Ramp, // 由疏到密
+    Wave, // 开头结尾稀疏，中间最密
+}
+
+impl SpawnCurve {
+    // 按本局已进行的比例算出这一轮最多出几只
+    pub fn cap(&self, fraction: f64, max_moles: usize) -> usize {
+        let fraction = fraction.clamp(0.0, 1.0);
+        let density = match self {
+            SpawnCurve::Flat => 1.0,
+            SpawnCurve::Ramp => fraction,
+            SpawnCurve::Wave => (PI * fraction).sin(),
+        };
+        1 + ((max_moles - 1) as f64 * density).round() as usize
+    }
+}
+
+// 每一轮出新地鼠之前撤掉哪些还在的地鼠
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum ClearStrategy {
+    All,     // 全部撤掉，所有地鼠同时缩回去
+    Expired, // 只撤掉已经待满 EXPIRE_ROUNDS 轮的
+    Never,   // 一直待到被打中为止
+}
+
+// 只撤到期的地鼠时，每只地鼠待几轮
+pub const EXPIRE_ROUNDS: u32 = 2;
+
+impl FromStr for ClearStrategy {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "all" => Ok(ClearStrategy::All),
+            "expired" => Ok(ClearStrategy::Expired),
+            "none" => Ok(ClearStrategy::Never),
+            _ => Err(format!(
+                "Unknown clear strategy: {} (expected all, expired or none)",
+                s
+            )),
+        }
+    }
+}
+
+impl FromStr for SpawnCurve {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "flat" => Ok(SpawnCurve::Flat),
+            "ramp" => Ok(SpawnCurve::Ramp),
+            "wave" => Ok(SpawnCurve::Wave),
+            _ => Err(format!(
+                "Unknown spawn curve: {} (expected flat, ramp or wave)",
+                s
+            )),
+        }
+    }
+}
@@ -0,0 +1,107 @@
+use std::{error::Error, fmt, io, path::PathBuf};
+
+// 退出码：正常退出和游戏结束为 0，出错时按原因区分，方便脚本判断
+const EXIT_INTERNAL: u8 = 1; // 程序自身的错误
+const EXIT_USAGE: u8 = 2; // 参数或布局文件有误
+const EXIT_TERMINAL: u8 = 3; // 不是终端、终端太小或不支持 raw 模式
+const EXIT_IO: u8 = 4; // 读写日志文件或终端失败
+
+#[derive(Debug)]
+pub enum GameError {
+    // 边框超出画面或者缩成了一条线
+    InvalidDimension {
+        top: usize,
+        bottom: usize,
+        left: usize,
+        right: usize,
+        width: usize,
+        height: usize,
+    },
+    ConfigParse(String),
+    InvalidLayout(String),
+    NotATerminal,
+    TerminalTooSmall {
+        columns: usize,
+        rows: usize,
+        width: usize,
+        height: usize,
+    },
+    RawModeUnsupported,
+    LogFile {
+        path: PathBuf,
+        source: io::Error,
+    },
+    Input(io::Error),
+    Output(io::Error),
+    SoakPanicked {
+        frame: u64,
+    },
+}
+
+impl GameError {
+    pub fn exit_code(&self) -> u8 {
+        match self {
+            GameError::InvalidDimension { .. } | GameError::SoakPanicked { .. } => EXIT_INTERNAL,
+            GameError::ConfigParse(_) | GameError::InvalidLayout(_) => EXIT_USAGE,
+            GameError::NotATerminal
+            | GameError::TerminalTooSmall { .. }
+            | GameError::RawModeUnsupported => EXIT_TERMINAL,
+            GameError::LogFile { .. } | GameError::Input(_) | GameError::Output(_) => EXIT_IO,
+        }
+    }
+}
+
+impl fmt::Display for GameError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            GameError::InvalidDimension {
+                top,
+                bottom,
+                left,
+                right,
+                width,
+                height,
+            } => write!(
+                f,
+                "\nCan not build the block! The parameters is incorrect!\nTraceBack:\n\tleft:{} right:{} width_limit:{}\n\ttop:{} bottom:{} height_limit:{}\n",
+                left, right, width, top, bottom, height
+            ),
+            GameError::ConfigParse(message) | GameError::InvalidLayout(message) => {
+                write!(f, "{}", message)
+            }
+            GameError::NotATerminal => {
+                write!(f, "whac-a-mole must be run in an interactive terminal")
+            }
+            GameError::TerminalTooSmall {
+                columns,
+                rows,
+                width,
+                height,
+            } => write!(
+                f,
+                "The terminal is {}x{}, but the game needs at least {}x{}",
+                columns, rows, width, height
+            ),
+            GameError::RawModeUnsupported => write!(f, "Your terminal does not support raw mode!"),
+            GameError::LogFile { path, source } => {
+                write!(f, "Can not open the log file {}: {}", path.display(), source)
+            }
+            GameError::Input(source) => write!(f, "Can not read the keyboard: {}", source),
+            GameError::Output(source) => write!(f, "Can not write to the terminal: {}", source),
+            GameError::SoakPanicked { frame } => {
+                write!(f, "The soak test panicked at frame {}", frame)
+            }
+        }
+    }
+}
+
+impl Error for GameError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            GameError::LogFile { source, .. }
+            | GameError::Input(source)
+            | GameError::Output(source) => Some(source),
+            _ => None,
+        }
+    }
+}
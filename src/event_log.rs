@@ -0,0 +1,44 @@
+use lazy_static::lazy_static;
+use std::{
+    fmt,
+    fs::{File, OpenOptions},
+    io::{self, Write},
+    path::Path,
+    sync::{Mutex, PoisonError},
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+lazy_static! {
+    static ref LOG_FILE: Mutex<Option<File>> = Mutex::new(None);
+}
+
+// 以追加方式打开日志文件，之后的 log_event! 才会真正写入
+pub fn open(path: &Path) -> io::Result<()> {
+    let file = OpenOptions::new().create(true).append(true).open(path)?;
+    *LOG_FILE.lock().unwrap_or_else(PoisonError::into_inner) = Some(file);
+    Ok(())
+}
+
+pub fn write(args: fmt::Arguments) {
+    let mut file = LOG_FILE.lock().unwrap_or_else(PoisonError::into_inner);
+    if let Some(file) = file.as_mut() {
+        let now = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap_or_default();
+        let _ = writeln!(
+            file,
+            "[{}.{:03}] {}",
+            now.as_secs(),
+            now.subsec_millis(),
+            args
+        );
+    }
+}
+
+macro_rules! log_event {
+    ($($arg:tt)*) => {
+        $crate::event_log::write(format_args!($($arg)*))
+    };
+}
+
+pub(crate) use log_event;
@@ -0,0 +1,42 @@
+// 一局里每一轮出完地鼠时的样子，按开局后走过的更新步数记下，暂停的那段不算
+#[derive(Clone, Debug, Default)]
+pub struct GhostRun {
+    rounds: Vec<Round>,
+    pub score: u128, // 这一局的最终得分，只有打破它的那一局才能取代它
+}
+
+#[derive(Clone, Debug)]
+struct Round {
+    step: u64,
+    holes: Vec<usize>, // 这一轮出完之后有地鼠的洞
+    score: u128,       // 出这一轮时的得分
+}
+
+impl GhostRun {
+    pub fn record(&mut self, step: u64, holes: Vec<usize>, score: u128) {
+        self.rounds.push(Round { step, holes, score });
+    }
+
+    // 回放到 step 这一刻：最近一轮有地鼠的洞和当时的得分，第一轮还没出时为 None
+    pub fn at(&self, step: u64) -> Option<(&[usize], u128)> {
+        let passed = self.rounds.partition_point(|round| round.step <= step);
+        let round = self.rounds.get(passed.checked_sub(1)?)?;
+        Some((&round.holes, round.score))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn replay_follows_the_recorded_rounds() {
+        let mut run = GhostRun::default();
+        run.record(60, vec![4], 0);
+        run.record(120, vec![0, 8], 10);
+        assert_eq!(run.at(59), None);
+        assert_eq!(run.at(60), Some(&[4][..]).map(|holes| (holes, 0)));
+        assert_eq!(run.at(119).unwrap().0, [4]);
+        assert_eq!(run.at(500), Some((&[0, 8][..], 10)));
+    }
+}
@@ -0,0 +1,63 @@
+use std::{
+    collections::VecDeque,
+    time::{Duration, Instant},
+};
+
+// 统计最近这段时间里打中了几只
+const WINDOW: Duration = Duration::from_secs(1);
+
+// 限制每秒最多打中几只，免得脚本连按刷出不可能的高分，超出的那几下直接忽略
+#[derive(Debug)]
+pub struct HitLimit {
+    max: usize,
+    hits: VecDeque<Instant>, // 最近一秒内每次打中的时间，从早到晚
+    pub dropped: u32,        // 因为超过上限被忽略的次数
+}
+
+impl HitLimit {
+    pub fn new(max: usize) -> Self {
+        HitLimit {
+            max,
+            hits: VecDeque::new(),
+            dropped: 0,
+        }
+    }
+
+    // 这一下还在上限以内就记下来并返回 true，超出的只计数
+    pub fn allow(&mut self, now: Instant) -> bool {
+        while matches!(self.hits.front(), Some(&at) if now.saturating_duration_since(at) >= WINDOW)
+        {
+            self.hits.pop_front();
+        }
+        if self.hits.len() >= self.max {
+            self.dropped += 1;
+            return false;
+        }
+        self.hits.push_back(now);
+        true
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn a_burst_is_cut_to_the_limit_per_second() {
+        let mut limit = HitLimit::new(3);
+        let start = Instant::now();
+        let ms = Duration::from_millis;
+        // 50ms 一下连按十下，前三下算数，之后一直到第一下满一秒都被忽略
+        let allowed: Vec<bool> = (0..10).map(|i| limit.allow(start + ms(50 * i))).collect();
+        assert_eq!(
+            allowed,
+            [true, true, true, false, false, false, false, false, false, false]
+        );
+        assert_eq!(limit.dropped, 7);
+        assert!(limit.allow(start + ms(1000)));
+        assert!(limit.allow(start + ms(1050)));
+        assert!(!limit.allow(start + ms(1060)));
+        assert!(limit.allow(start + ms(1100)));
+        assert_eq!(limit.dropped, 8);
+    }
+}
@@ -0,0 +1,72 @@
+use std::str::FromStr;
+
+use crate::{
+    BEAT_ROW, COMBO_ROW, DAILY_ROW, GHOST_ROW, HELP_ROW, LIVES_ROW, MINI_MAP_ROW, RATE_ROW,
+    STATS_ROW, STATS_ROWS, TARGET_ROW, WAVE_ROW,
+};
+
+// 对局中信息栏显示多少内容，局中可以按键切换，结算画面不受影响
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Hud {
+    Minimal,  // 只有得分、时间和状态
+    Standard, // 原来的信息栏
+    Detailed, // 再加上命中、打空、命中率和平均反应时间
+}
+
+impl Hud {
+    pub fn next(self) -> Hud {
+        match self {
+            Hud::Minimal => Hud::Standard,
+            Hud::Standard => Hud::Detailed,
+            Hud::Detailed => Hud::Minimal,
+        }
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Hud::Minimal => "minimal",
+            Hud::Standard => "standard",
+            Hud::Detailed => "detailed",
+        }
+    }
+
+    // 这一档在对局中不显示的行
+    pub fn hidden_rows(self) -> Vec<usize> {
+        let stats = STATS_ROW..STATS_ROW + STATS_ROWS;
+        match self {
+            Hud::Minimal => [
+                COMBO_ROW,
+                MINI_MAP_ROW,
+                WAVE_ROW,
+                RATE_ROW,
+                TARGET_ROW,
+                BEAT_ROW,
+                LIVES_ROW,
+                GHOST_ROW,
+                DAILY_ROW,
+            ]
+            .into_iter()
+            .chain(HELP_ROW..HELP_ROW + 4)
+            .chain(stats)
+            .collect(),
+            Hud::Standard => stats.collect(),
+            Hud::Detailed => vec![],
+        }
+    }
+}
+
+impl FromStr for Hud {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "minimal" => Ok(Hud::Minimal),
+            "standard" => Ok(Hud::Standard),
+            "detailed" => Ok(Hud::Detailed),
+            _ => Err(format!(
+                "Unknown HUD level: {} (expected minimal, standard or detailed)",
+                s
+            )),
+        }
+    }
+}
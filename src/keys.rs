@@ -0,0 +1,104 @@
+use std::str::FromStr;
+
+use crate::{COLUMN_KEYS, ROW_KEYS};
+
+// 重开、清空成绩、单键模式的空格和打开说明的 h 不开放重映射，但也不能被占用
+const RESERVED_KEYS: [char; 4] = ['r', 'e', ' ', 'h'];
+
+// 控制键，默认与原来写死的按键一致
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct KeyBindings {
+    pub quit: char,
+    pub pause: char,
+    pub reveal: char, // 只有打开 --cheats 时才有用
+    pub mute: char,
+    pub help: char, // h 也能打开说明
+    pub hud: char,
+}
+
+impl Default for KeyBindings {
+    fn default() -> Self {
+        KeyBindings {
+            quit: 'q',
+            pause: 'p',
+            reveal: '`',
+            mute: 'm',
+            help: '?',
+            hud: 'i',
+        }
+    }
+}
+
+impl KeyBindings {
+    // 解析形如 quit=Q 的一条绑定
+    pub fn bind(&mut self, spec: &str) -> Result<(), String> {
+        let (action, key) = spec
+            .split_once('=')
+            .ok_or_else(|| format!("Invalid key binding: {}", spec))?;
+        let mut chars = key.chars();
+        let key = match (chars.next(), chars.next()) {
+            (Some(key), None) => key,
+            _ => return Err(format!("A key binding needs exactly one key: {}", spec)),
+        };
+        match action {
+            "quit" => self.quit = key,
+            "pause" => self.pause = key,
+            "reveal" => self.reveal = key,
+            "mute" => self.mute = key,
+            "help" => self.help = key,
+            "hud" => self.hud = key,
+            _ => return Err(format!("Unknown action: {}", action)),
+        }
+        Ok(())
+    }
+
+    // 控制键不能和打地鼠的键或者彼此冲突
+    pub fn validate(&self) -> Result<(), String> {
+        let bindings = [
+            ("quit", self.quit),
+            ("pause", self.pause),
+            ("reveal", self.reveal),
+            ("mute", self.mute),
+            ("help", self.help),
+            ("hud", self.hud),
+        ];
+        for (i, &(action, key)) in bindings.iter().enumerate() {
+            if key.is_ascii_digit()
+                || ROW_KEYS.contains(&key)
+                || COLUMN_KEYS.contains(&key)
+                || RESERVED_KEYS.contains(&key)
+            {
+                return Err(format!("Key '{}' for {} is already in use", key, action));
+            }
+            if let Some((other, _)) = bindings[..i].iter().find(|&&(_, k)| k == key) {
+                return Err(format!(
+                    "{} and {} are both bound to '{}'",
+                    other, action, key
+                ));
+            }
+        }
+        Ok(())
+    }
+}
+
+// 按住不放时终端发来的重复按键怎么处理
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum KeyRepeat {
+    Ignore, // 只算第一下，地鼠被打掉后按住也不会记成打空
+    Whack,  // 每次重复都当成又按了一下
+}
+
+impl FromStr for KeyRepeat {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "ignore" => Ok(KeyRepeat::Ignore),
+            "whack" => Ok(KeyRepeat::Whack),
+            _ => Err(format!(
+                "Unknown key repeat mode: {} (expected ignore or whack)",
+                s
+            )),
+        }
+    }
+}
@@ -0,0 +1,56 @@
+use std::{env, str::FromStr};
+
+use crate::caps;
+
+// 界面语言，目前只影响终端标题
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Lang {
+    En,
+    Zh,
+}
+
+impl Lang {
+    // 没有指定 --lang 时按 locale 猜，中文环境用中文，其余用英文
+    pub fn detect() -> Self {
+        Lang::from_locale(&caps::locale(&|name| env::var(name).ok()))
+    }
+
+    pub fn from_locale(locale: &str) -> Self {
+        if locale.starts_with("zh") {
+            Lang::Zh
+        } else {
+            Lang::En
+        }
+    }
+
+    pub fn title(&self) -> &'static str {
+        match self {
+            Lang::En => "Whac-A-Mole",
+            Lang::Zh => "打地鼠",
+        }
+    }
+}
+
+impl FromStr for Lang {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "en" => Ok(Lang::En),
+            "zh" => Ok(Lang::Zh),
+            _ => Err(format!("Unknown language: {} (expected en or zh)", s)),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn chinese_locales_get_the_chinese_title() {
+        assert_eq!(Lang::from_locale("zh_cn.utf-8").title(), "打地鼠");
+        assert_eq!(Lang::from_locale("en_us.utf-8"), Lang::En);
+        assert_eq!(Lang::from_locale(""), Lang::En);
+    }
+}
@@ -0,0 +1,344 @@
+use std::{fs, path::Path, str::FromStr};
+
+use crate::{Dimension, DIVIDER, HOLE_WIDTH};
+
+// 每个洞出现地鼠的权重，顺序与数字键一致，中间的洞最容易出现，四个角最少
+const HOLE_SPAWN_WEIGHTS: [u32; 9] = [2, 3, 2, 3, 4, 3, 2, 3, 2];
+
+// 数字键只有 1-9
+pub const MAX_HOLES: usize = 9;
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct HoleRect {
+    pub top: usize,
+    pub bottom: usize,
+    pub left: usize,
+    pub right: usize,
+    pub x: usize, // 地鼠出现的位置
+    pub y: usize,
+    pub weight: u32,
+}
+
+// 默认布局里相邻两个洞的边框之间空出几列、几行
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Gap {
+    pub columns: usize,
+    pub rows: usize,
+}
+
+pub const DEFAULT_GAP: Gap = Gap {
+    columns: 3,
+    rows: 1,
+};
+
+impl FromStr for Gap {
+    type Err = String;
+
+    // 写成 列,行，比如 3,1
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let invalid = || format!("Invalid gap {:?}, expected columns,rows", s);
+        let (columns, rows) = s.split_once(',').ok_or_else(invalid)?;
+        Ok(Gap {
+            columns: columns.trim().parse().map_err(|_| invalid())?,
+            rows: rows.trim().parse().map_err(|_| invalid())?,
+        })
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct Layout {
+    pub holes: Vec<HoleRect>,
+}
+
+impl HoleRect {
+    // 两个洞可以共用边框，但内部不能重叠
+    fn overlaps(&self, other: &HoleRect) -> bool {
+        // 直接比较，不做减法，坐标再大也不会回绕
+        self.top.max(other.top) < self.bottom.min(other.bottom)
+            && self.left.max(other.left) < self.right.min(other.right)
+    }
+}
+
+impl Layout {
+    // 默认的 3x3 布局，间距为 DEFAULT_GAP 时与 layouts/classic.txt 一致
+    // 间距大到算出来的坐标溢出时报错，放不放得下留给 validate 判断
+    pub fn grid(gap: Gap) -> Result<Self, String> {
+        let initial_top: usize = 3;
+        let initial_bottom: usize = 7;
+        let initial_left: usize = 3;
+        let initial_right: usize = 11;
+        let overflow = || {
+            format!(
+                "Gap {},{} is too large for the board",
+                gap.columns, gap.rows
+            )
+        };
+        let offset = |initial: usize, increment: usize, vector: usize| {
+            increment
+                .checked_mul(vector)
+                .and_then(|offset| initial.checked_add(offset))
+                .ok_or_else(overflow)
+        };
+        // 下一个洞的左边框紧跟在这个洞的右边框和间距之后
+        let horizontal_increment = (initial_right - initial_left + 1)
+            .checked_add(gap.columns)
+            .ok_or_else(overflow)?;
+        let vertical_increment = (initial_bottom - initial_top + 1)
+            .checked_add(gap.rows)
+            .ok_or_else(overflow)?;
+        let mut holes = vec![];
+        for (i, &weight) in HOLE_SPAWN_WEIGHTS.iter().enumerate() {
+            let horizontal_vector = i % 3;
+            let vertical_vector = i / 3;
+            let top = offset(initial_top, vertical_increment, vertical_vector)?;
+            let bottom = offset(initial_bottom, vertical_increment, vertical_vector)?;
+            let left = offset(initial_left, horizontal_increment, horizontal_vector)?;
+            let right = offset(initial_right, horizontal_increment, horizontal_vector)?;
+            holes.push(HoleRect {
+                top,
+                bottom,
+                left,
+                right,
+                x: left + (right - left) / 2,
+                y: top + (bottom - top) / 2,
+                weight,
+            });
+        }
+        Ok(Layout { holes })
+    }
+
+    // 左右翻转整个棋盘，同一行里连着的几个洞倒过来编号，数字键仍然从左往右数
+    // 在分隔线右边的洞翻过去坐标会变成负数，这时报错而不是回绕成一个很大的数
+    pub fn mirror(&mut self) -> Result<(), String> {
+        for (i, hole) in self.holes.iter_mut().enumerate() {
+            let outside = || format!("Hole {} does not fit inside the board", i + 1);
+            let flip = |at: usize| DIVIDER.checked_sub(at).ok_or_else(outside);
+            (hole.left, hole.right) = (flip(hole.right)?, flip(hole.left)?);
+            hole.x = hole
+                .x
+                .checked_add(HOLE_WIDTH - 1)
+                .and_then(|right| DIVIDER.checked_sub(right))
+                .ok_or_else(outside)?;
+        }
+        let mut start = 0;
+        while start < self.holes.len() {
+            let y = self.holes[start].y;
+            let len = self.holes[start..]
+                .iter()
+                .take_while(|hole| hole.y == y)
+                .count();
+            self.holes[start..start + len].reverse();
+            start += len;
+        }
+        Ok(())
+    }
+
+    pub fn load(path: &Path) -> Result<Self, String> {
+        let text = fs::read_to_string(path)
+            .map_err(|err| format!("Can not read the layout {}: {}", path.display(), err))?;
+        Layout::parse(&text)
+    }
+
+    // 每行一个洞：top bottom left right x y [weight]，# 开头的是注释
+    pub fn parse(text: &str) -> Result<Self, String> {
+        let mut holes = vec![];
+        for (i, line) in text.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            let numbers = line
+                .split_whitespace()
+                .map(|field| field.parse::<usize>())
+                .collect::<Result<Vec<_>, _>>()
+                .map_err(|_| format!("Layout line {}: expected numbers, got {:?}", i + 1, line))?;
+            let weight = match numbers.len() {
+                6 => 1,
+                7 => u32::try_from(numbers[6]).map_err(|_| {
+                    format!(
+                        "Layout line {}: weight {} is too large (at most {})",
+                        i + 1,
+                        numbers[6],
+                        u32::MAX
+                    )
+                })?,
+                _ => {
+                    return Err(format!(
+                        "Layout line {}: expected 6 or 7 numbers, got {}",
+                        i + 1,
+                        numbers.len()
+                    ))
+                }
+            };
+            holes.push(HoleRect {
+                top: numbers[0],
+                bottom: numbers[1],
+                left: numbers[2],
+                right: numbers[3],
+                x: numbers[4],
+                y: numbers[5],
+                weight,
+            });
+        }
+        Ok(Layout { holes })
+    }
+
+    // 洞必须都在分隔线左侧的棋盘内，地鼠要放得进洞里，洞与洞之间不能重叠
+    pub fn validate(&self, size: &Dimension) -> Result<(), String> {
+        if self.holes.is_empty() || self.holes.len() > MAX_HOLES {
+            return Err(format!(
+                "A layout needs 1 to {} holes, got {}",
+                MAX_HOLES,
+                self.holes.len()
+            ));
+        }
+        for (i, hole) in self.holes.iter().enumerate() {
+            if hole.top == 0
+                || hole.left == 0
+                || hole.bottom >= size.height.saturating_sub(1)
+                || hole.right >= DIVIDER
+            {
+                return Err(format!("Hole {} does not fit inside the board", i + 1));
+            }
+            if hole.top >= hole.bottom || hole.left >= hole.right {
+                return Err(format!("Hole {} has an empty rectangle", i + 1));
+            }
+            if hole.y <= hole.top
+                || hole.y >= hole.bottom
+                || hole.x <= hole.left
+                || hole.x >= hole.right.saturating_sub(HOLE_WIDTH - 1)
+            {
+                return Err(format!("The mole of hole {} is outside its box", i + 1));
+            }
+        }
+        // 重叠的边框合并出来的交叉是乱的，一次列出所有重叠的洞，免得改一处报一处
+        let overlapping: Vec<String> = self
+            .holes
+            .iter()
+            .enumerate()
+            .flat_map(|(i, hole)| {
+                self.holes
+                    .iter()
+                    .enumerate()
+                    .skip(i + 1)
+                    .filter(move |(_, other)| hole.overlaps(other))
+                    .map(move |(j, _)| format!("{} and {}", i + 1, j + 1))
+            })
+            .collect();
+        if !overlapping.is_empty() {
+            return Err(format!("Holes overlap: {}", overlapping.join(", ")));
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn classic_layout_file_matches_the_grid() {
+        let layout = Layout::parse(include_str!("../layouts/classic.txt")).unwrap();
+        assert_eq!(layout, Layout::grid(DEFAULT_GAP).unwrap());
+        assert!(layout
+            .validate(&Dimension {
+                width: 70,
+                height: 25
+            })
+            .is_ok());
+    }
+
+    #[test]
+    fn mirrored_grid_keeps_digits_left_to_right() {
+        let mut layout = Layout::grid(DEFAULT_GAP).unwrap();
+        layout.mirror().unwrap();
+        assert!(layout
+            .validate(&Dimension {
+                width: 70,
+                height: 25
+            })
+            .is_ok());
+        let xs: Vec<usize> = layout.holes[..3].iter().map(|hole| hole.x).collect();
+        assert_eq!(xs, [8, 20, 32]);
+        let weights: Vec<u32> = layout.holes.iter().map(|hole| hole.weight).collect();
+        assert_eq!(weights, HOLE_SPAWN_WEIGHTS);
+    }
+
+    #[test]
+    fn huge_coordinates_are_errors_not_panics() {
+        let size = Dimension {
+            width: 70,
+            height: 25,
+        };
+        let layout = Layout::parse(&format!("3 {} 3 11 7 5", usize::MAX)).unwrap();
+        assert_eq!(
+            layout.validate(&size).unwrap_err(),
+            "Hole 1 does not fit inside the board"
+        );
+        assert_eq!(
+            Layout::parse("3 7 3 11 7 5 4294967296").unwrap_err(),
+            "Layout line 1: weight 4294967296 is too large (at most 4294967295)"
+        );
+        let layout = Layout::parse(&format!("3 7 3 11 {} 5", usize::MAX)).unwrap();
+        assert_eq!(
+            layout.validate(&size).unwrap_err(),
+            "The mole of hole 1 is outside its box"
+        );
+    }
+
+    #[test]
+    fn overlapping_holes_are_all_reported() {
+        let size = Dimension {
+            width: 70,
+            height: 25,
+        };
+        // 第 1、2 个洞共用一条边框不算重叠，第 3 个洞压在两个洞上，第 4 个洞和第 1 个洞一模一样
+        let layout =
+            Layout::parse("3 7 3 11 7 5\n3 7 11 19 15 5\n5 9 9 14 11 7\n3 7 3 11 7 5").unwrap();
+        assert_eq!(
+            layout.validate(&size).unwrap_err(),
+            "Holes overlap: 1 and 3, 1 and 4, 2 and 3, 3 and 4"
+        );
+        let layout = Layout::parse("3 7 3 11 7 5\n3 7 11 19 15 5").unwrap();
+        assert!(layout.validate(&size).is_ok());
+    }
+
+    #[test]
+    fn coordinates_that_would_wrap_are_errors() {
+        let gap = Gap {
+            columns: usize::MAX / 2,
+            rows: 1,
+        };
+        assert_eq!(
+            Layout::grid(gap).unwrap_err(),
+            format!("Gap {},1 is too large for the board", usize::MAX / 2)
+        );
+        // 分隔线右边的洞翻到左边会是负数
+        let mut layout = Layout::parse("3 7 3 11 7 5\n3 7 40 48 44 5").unwrap();
+        assert_eq!(
+            layout.mirror().unwrap_err(),
+            "Hole 2 does not fit inside the board"
+        );
+        // 布局文件里的大数在 validate 里也只是报错
+        let size = Dimension {
+            width: 70,
+            height: 25,
+        };
+        let layout = Layout::parse(&format!("3 7 3 11 7 5\n3 {0} 3 {0} 7 5", usize::MAX)).unwrap();
+        assert_eq!(
+            layout.validate(&size).unwrap_err(),
+            "Hole 2 does not fit inside the board"
+        );
+        let huge = HoleRect {
+            top: usize::MAX - 1,
+            bottom: usize::MAX,
+            left: usize::MAX - 1,
+            right: usize::MAX,
+            x: 0,
+            y: 0,
+            weight: 1,
+        };
+        assert!(huge.overlaps(&huge.clone()));
+        assert!(!huge.overlaps(&layout.holes[0]));
+    }
+}
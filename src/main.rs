@@ -1,60 +1,451 @@
 use crossterm::{
-    cursor::{Hide, MoveTo, MoveToNextLine},
-    event::{read, Event, KeyCode},
-    execute,
-    style::style,
+    cursor::{Hide, MoveTo, MoveToNextLine, Show},
+    event::{
+        poll, read, DisableFocusChange, DisableMouseCapture, EnableFocusChange, EnableMouseCapture,
+        Event, KeyCode, KeyEventKind, KeyModifiers, KeyboardEnhancementFlags, MouseButton,
+        MouseEvent, MouseEventKind, PopKeyboardEnhancementFlags, PushKeyboardEnhancementFlags,
+    },
+    execute, queue,
+    style::{style, Color, Stylize},
     terminal::{
-        disable_raw_mode, enable_raw_mode, Clear, ClearType, EnterAlternateScreen,
-        LeaveAlternateScreen, SetTitle,
+        self, disable_raw_mode, enable_raw_mode, is_raw_mode_enabled, Clear, ClearType,
+        EnterAlternateScreen, LeaveAlternateScreen, SetTitle,
     },
-    Result,
 };
-use drawille::{Canvas};
 use lazy_static::lazy_static;
-use rand::Rng;
+use rand::{
+    distributions::WeightedIndex, prelude::Distribution, rngs::StdRng, seq::SliceRandom, Rng,
+    SeedableRng,
+};
 use std::{
-    io::stdout,
-    process::exit,
-    sync::{Arc, Mutex},
+    borrow::Cow,
+    cell::Cell,
+    cmp::Reverse,
+    env,
+    io::{self, stdin, stdout, IsTerminal, Write},
+    mem,
+    ops::{Deref, DerefMut, Range, RangeInclusive},
+    panic,
+    process::ExitCode,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc, Mutex, MutexGuard, PoisonError,
+    },
     thread::{self},
-    time::Duration,
+    time::{Duration, Instant},
 };
+use unicode_width::{UnicodeWidthChar, UnicodeWidthStr};
+
+mod achievements;
+mod banner;
+#[cfg(feature = "render-bench")]
+mod bench;
+mod best_times;
+mod big_digits;
+mod caps;
+mod daily;
+mod difficulty;
+mod error;
+mod event_log;
+mod ghost;
+mod hit_limit;
+mod hud;
+mod keys;
+mod lang;
+mod layout;
+mod options;
+mod soak;
+mod theme;
+
+use achievements::{Achievement, Achievements};
+use best_times::BestTimes;
+use big_digits::BIG_ROWS;
+use caps::{Capabilities, Glyphs, EMOJI_GLYPHS};
+use difficulty::{ClearStrategy, DEFAULT_DIFFICULTY, DIFFICULTIES, EXPIRE_ROUNDS};
+use error::GameError;
+use event_log::log_event;
+use ghost::GhostRun;
+use hit_limit::HitLimit;
+use keys::KeyRepeat;
+use lang::Lang;
+use layout::{HoleRect, Layout};
+use options::{Options, TimerDisplay};
+use theme::{Theme, DEFAULT_THEME, THEMES};
 
 lazy_static! {
-    static ref GAME: Arc<Mutex<Game>> = Arc::new(Mutex::new(Game::new(&Dimension {
-        width: 70,
-        height: 25,
-    })));
+    static ref GAME: Arc<Mutex<Game>> = Arc::new(Mutex::new(Game::new(&BOARD_SIZE)));
+    // 画面写到哪里，平时是终端，换成别的 Write 就能把整局画面录下来
+    static ref OUTPUT: Mutex<Box<dyn Write + Send>> = Mutex::new(Box::new(stdout()));
+}
+
+// 游戏的锁由输入循环和每局的更新线程共用，规矩是：
+// 拿着锁时不等按键也不睡眠，要用的东西先拷出来再放开锁，否则另一边会一直卡着；
+// 同一个线程不能再锁一次，标准库的 Mutex 会直接死锁。调试构建里这两条都有断言检查
+thread_local! {
+    static HOLDS_GAME: Cell<bool> = const { Cell::new(false) };
+}
+
+// 放开时记下这个线程已经不拿着锁了
+struct GameGuard(MutexGuard<'static, Game>);
+
+impl Deref for GameGuard {
+    type Target = Game;
+
+    fn deref(&self) -> &Game {
+        &self.0
+    }
+}
+
+impl DerefMut for GameGuard {
+    fn deref_mut(&mut self) -> &mut Game {
+        &mut self.0
+    }
+}
+
+impl Drop for GameGuard {
+    fn drop(&mut self) {
+        HOLDS_GAME.with(|holds| holds.set(false));
+    }
+}
+
+// 别的线程拿着锁时 panic 了也照样取出数据接着用，不让其他线程跟着一起崩
+fn lock_game() -> GameGuard {
+    debug_assert!(
+        !HOLDS_GAME.with(Cell::get),
+        "the game is already locked by this thread"
+    );
+    let guard = GAME.lock().unwrap_or_else(PoisonError::into_inner);
+    HOLDS_GAME.with(|holds| holds.set(true));
+    GameGuard(guard)
+}
+
+// 等按键和睡眠之前调用
+fn assert_game_unlocked() {
+    debug_assert!(
+        !HOLDS_GAME.with(Cell::get),
+        "the game must not stay locked while waiting"
+    );
+}
+
+// 先锁游戏再锁输出，反过来会死锁；execute! 会把参数求值两次，要先取到变量里再用
+fn output() -> MutexGuard<'static, Box<dyn Write + Send>> {
+    OUTPUT.lock().unwrap_or_else(PoisonError::into_inner)
 }
 
+const BOARD_SIZE: Dimension = Dimension {
+    width: 70,
+    height: 25,
+};
+
 // https://unicode-table.com/cn/blocks/box-drawing/
 const CHAR_VIEW_LIST: [char; 16] = [
     ' ', '上', '下', '║', '左', '╚', '╔', '╠', '右', '╝', '╗', '╣', '═', '╩', '╦', '╬',
 ];
 
+// 一次打掉整行/整列的按键，每只地鼠的得分减半
+const ROW_KEYS: [char; 3] = ['a', 's', 'd'];
+const COLUMN_KEYS: [char; 3] = ['z', 'x', 'c'];
+// 默认每打中一只的得分，可以用 --points-per-hit 改
+const HIT_POINTS: u128 = 10;
+// 地鼠出现前在洞口显示的提示
+const TELL_GLYPH: &str = "*";
+// 洞口固定占两格，刚好放下一个宽字符
+const HOLE_WIDTH: usize = 2;
+// 精简模式只画一行，超出这么宽的部分截掉
+const COMPACT_SIZE: Dimension = Dimension {
+    width: 64,
+    height: 1,
+};
+// 宽字符右半边占位的格子，渲染时跳过
+const WIDE_TAIL: char = '\0';
+// 时间到时离目标分数不超过这么多次命中的得分才进入加时
+const OVERTIME_HITS: u128 = 3;
+// 一局的秒数
+const ROUND_TIME: u8 = 60;
+// 更新循环每秒 60 步，落后太多时最多补这么多步
+const UPDATE_STEP: Duration = Duration::from_micros(16_667);
+const MAX_CATCH_UP: u32 = 10;
+// 重画频率的上限，再高也没用，一帧里至少要走一步更新
+const MAX_FPS: u32 = 60;
+// 最后几秒的倒计时精确到十分之一秒
+const FINE_TIME: u8 = 5;
+// 无尽模式每一波的秒数、波与波之间的休息时间和出地鼠间隔的下限
+const WAVE_TIME: u8 = 20;
+const WAVE_BREAK: Duration = Duration::from_secs(3);
+const MIN_SPAWN_INTERVAL: Duration = Duration::from_millis(300);
+// 禅模式下空洞缓缓转动的字符和每一帧的时长
+const ZEN_FRAMES: [&str; 4] = ["-", "\\", "|", "/"];
+const ZEN_TICK: Duration = Duration::from_millis(250);
+// 打中后压扁动画每一帧的时长，放完后变回空洞
+const SQUISH_FRAME: Duration = Duration::from_millis(120);
+// 断了连击的提示显示多久
+const COMBO_LOST_TIME: Duration = Duration::from_millis(800);
+// 静音提示在状态行上停留的时间
+const NOTICE_TIME: Duration = Duration::from_secs(1);
+// 展示模式下等按键时多久醒一次
+const KIOSK_POLL: Duration = Duration::from_millis(200);
+// 开了扣分模式时，这么久没打中就开始扣分
+const DECAY_IDLE: Duration = Duration::from_secs(3);
+// 热力图的深浅档数，和 Glyphs::shades 的长度一致
+const HEAT_LEVELS: usize = 4;
+// 滚动计分每一帧走剩下差距的几分之一
+const COUNT_UP_STEPS: u128 = 4;
+// 地鼠溜走的标记显示多久
+const ESCAPE_TIME: Duration = Duration::from_millis(300);
+// 开场动画每画一行的时长
+const INTRO_FRAME: Duration = Duration::from_millis(20);
+// 动画每一帧的时长
+const ANIMATION_TICK: Duration = Duration::from_millis(40);
+
+// 赢了之后结果的颜色，不随主题变化
+const WIN_COLOR: Color = Color::Green;
+// 弹出说明框时框外内容的颜色
+const DIM_COLOR: Color = Color::DarkGrey;
+
+// 左侧棋盘和右侧信息栏的分隔线所在列
+const DIVIDER: usize = 40;
+// 信息栏离分隔线的距离和最长一行文字的宽度
+const INFO_GAP: usize = 10;
+const INFO_WIDTH: usize = 20;
+// 成就横幅在棋盘里的行，默认布局里这一行在洞的上方，是空的
+const ACHIEVEMENT_ROW: usize = 1;
+// 右侧信息栏的各行
+const BIG_SCORE_ROW: usize = 1;
+const BIG_TIME_ROW: usize = BIG_SCORE_ROW + BIG_ROWS;
+const STATUS_ROW: usize = 5;
+const COMBO_ROW: usize = 6;
+const MINI_MAP_ROW: usize = 7;
+const WAVE_ROW: usize = 8;
+const SCORE_ROW: usize = 9;
+const RATE_ROW: usize = 10;
+const TIME_ROW: usize = 11;
+const TARGET_ROW: usize = 12;
+const HELP_ROW: usize = 13;
+const SUMMARY_ROW: usize = 17;
+const SUMMARY_ROWS: usize = 6;
+const DAILY_ROW: usize = SUMMARY_ROW + SUMMARY_ROWS;
+// 节拍条只在对局中显示，结算时让给结算信息
+const BEAT_ROW: usize = SUMMARY_ROW;
+const BEAT_STEPS: usize = 4;
+// 剩下几条命也只在对局中显示，紧跟在节拍条下面
+const LIVES_ROW: usize = BEAT_ROW + 1;
+// 和重影比分也只在对局中显示
+const GHOST_ROW: usize = LIVES_ROW + 1;
+// 详细档的命中统计也只在对局中显示
+const STATS_ROW: usize = GHOST_ROW + 1;
+const STATS_ROWS: usize = 3;
+
 type Matrix<T> = Vec<Vec<T>>;
 
+// 开局时棋盘从上到下一行一行画出来，按任意键直接画完
+fn play_intro(view: &GameView) {
+    {
+        let mut out = output();
+        let _ = execute!(out, Clear(ClearType::All));
+    }
+    // 精简模式只有一行，没什么可逐行画的
+    if view.compact {
+        view.draw();
+        return;
+    }
+    for y in 0..view.views.len() {
+        view.draw_rows(&[y]);
+        assert_game_unlocked();
+        if !poll(INTRO_FRAME).unwrap_or(false) {
+            continue;
+        }
+        // 按下开局的那个键松开时也会有事件，只有新按下的键才算跳过
+        if let Ok(Event::Key(key_event)) = read() {
+            if key_event.kind == KeyEventKind::Press {
+                break;
+            }
+        }
+    }
+    view.draw();
+}
+
+// 有的伪终端报告 0x0，查不到或者有一边是 0 时当作大小未知，记一笔日志由调用方按棋盘大小处理
+fn plausible_size(queried: io::Result<(u16, u16)>) -> Option<Dimension> {
+    match queried {
+        Ok((columns, rows)) if columns > 0 && rows > 0 => Some(Dimension {
+            width: columns.into(),
+            height: rows.into(),
+        }),
+        other => {
+            log_event!(
+                "terminal size {:?} is unusable, assuming {}x{}",
+                other,
+                BOARD_SIZE.width,
+                BOARD_SIZE.height
+            );
+            None
+        }
+    }
+}
+
+// 横幅盖住整个画面，最后一行是提示；raw 模式下换行不回到行首，每行都要单独移到下一行开头
+fn draw_banner(text: &str, prompt: &str) {
+    let Dimension {
+        width: columns,
+        height: rows,
+    } = plausible_size(terminal::size()).unwrap_or(BOARD_SIZE);
+    let mut out = output();
+    let _ = queue!(out, MoveTo(0, 0), Clear(ClearType::All));
+    let lines = banner::render(text, columns, rows.saturating_sub(2));
+    for line in lines {
+        let _ = write!(out, "{}", line);
+        let _ = queue!(out, MoveToNextLine(1));
+    }
+    let _ = queue!(out, MoveToNextLine(1));
+    let _ = write!(out, "{}", banner::render(prompt, columns, 1).concat());
+    let _ = out.flush();
+}
+
+// 终端响铃，大多数终端会发出提示音或者闪一下屏幕
+fn beep() {
+    let mut out = output();
+    let _ = write!(out, "\x07");
+    let _ = out.flush();
+}
+
 fn clear_terminal() {
-    let _ = execute!(stdout(), MoveTo(0, 0));
+    let mut out = output();
+    let _ = execute!(out, MoveTo(0, 0));
+}
+
+// 终端不支持备用屏幕时直接在当前屏幕上画，退出时清屏
+static ALT_SCREEN: AtomicBool = AtomicBool::new(true);
+// 打开 --mouse 时才接管鼠标，否则不影响在终端里选中文字
+static MOUSE: AtomicBool = AtomicBool::new(false);
+
+// 支持的终端会区分按下、按住重复和松开，也会报告窗口有没有焦点，不支持的终端会忽略这些请求
+// 进不了备用屏幕时退回普通屏幕，清屏后照常画；其他写入失败说明终端用不了，交给调用方报错
+fn go_alternate_screen(out: &mut impl Write) -> io::Result<()> {
+    if ALT_SCREEN.load(Ordering::Relaxed) {
+        if let Err(err) = execute!(out, EnterAlternateScreen) {
+            log_event!(
+                "alternate screen unavailable, using the normal screen: {}",
+                err
+            );
+            ALT_SCREEN.store(false, Ordering::Relaxed);
+        }
+    }
+    if !ALT_SCREEN.load(Ordering::Relaxed) {
+        execute!(out, Clear(ClearType::All))?;
+    }
+    execute!(
+        out,
+        Hide,
+        EnableFocusChange,
+        PushKeyboardEnhancementFlags(KeyboardEnhancementFlags::REPORT_EVENT_TYPES)
+    )?;
+    if MOUSE.load(Ordering::Relaxed) {
+        execute!(out, EnableMouseCapture)?;
+    }
+    Ok(())
+}
+
+// 恢复时一步失败也接着做完其余几步，返回第一个错误
+fn leave_alternate_screen(out: &mut impl Write) -> io::Result<()> {
+    let mut result = Ok(());
+    if MOUSE.load(Ordering::Relaxed) {
+        result = result.and(execute!(out, DisableMouseCapture));
+    }
+    result = result.and(execute!(
+        out,
+        PopKeyboardEnhancementFlags,
+        DisableFocusChange,
+        Show
+    ));
+    if ALT_SCREEN.load(Ordering::Relaxed) {
+        result.and(execute!(out, LeaveAlternateScreen))
+    } else {
+        result.and(execute!(out, Clear(ClearType::All), MoveTo(0, 0)))
+    }
+}
+
+fn get_random_num<R: Rng>(rng: &mut R, from: usize, to: usize) -> usize {
+    rng.gen_range(from..=to)
 }
 
-fn go_alternate_screen() {
-    let _ = execute!(stdout(), EnterAlternateScreen, Hide);
+// 按权重随机选一个洞，权重全为 0 时退化为均匀随机
+fn get_weighted_hole_idx<R: Rng>(rng: &mut R, weights: &[u32]) -> usize {
+    match WeightedIndex::new(weights) {
+        Ok(dist) => dist.sample(rng),
+        Err(_) => rng.gen_range(0..weights.len()),
+    }
 }
 
-fn leave_alternate_screen() {
-    let _ = execute!(stdout(), LeaveAlternateScreen);
+// 整行/整列按键对应的洞
+fn get_sweep_holes(key: char) -> Option<[usize; 3]> {
+    if let Some(row) = ROW_KEYS.iter().position(|&k| k == key) {
+        return Some([row * 3, row * 3 + 1, row * 3 + 2]);
+    }
+    if let Some(column) = COLUMN_KEYS.iter().position(|&k| k == key) {
+        return Some([column, column + 3, column + 6]);
+    }
+    None
 }
 
-fn get_random_num(from: usize, to: usize) -> usize {
-    return rand::thread_rng().gen_range(from..=to);
+// 默认颜色不输出转义序列，保持画面字符串干净
+fn paint(ch: char, color: Color) -> String {
+    if color == Color::Reset {
+        return ch.to_string();
+    }
+    style(ch).with(color).to_string()
 }
 
+// 宽字符占两格，右边一格填 WIDE_TAIL；超出画面的部分直接丢掉
 fn write_words(views: &mut Matrix<char>, left: usize, top: usize, words: String) {
-    for (i, ch) in words.chars().enumerate() {
+    let row = match views.get_mut(top) {
+        Some(row) => row,
+        None => return,
+    };
+    let mut x = left;
+    for ch in words.chars() {
+        let width = ch.width().unwrap_or(0);
+        if width == 0 {
+            continue;
+        }
+        if x + width > row.len() {
+            break;
+        }
+        // 盖住了别的宽字符的一半时，把剩下的一半也擦掉；前一半刚被这次写掉的不用管
+        if row[x] == WIDE_TAIL && x == left && x > 0 {
+            row[x - 1] = ' ';
+        }
         // 数组是横着放的
-        views[top][left + i] = ch;
+        row[x] = ch;
+        if width == 2 {
+            row[x + 1] = WIDE_TAIL;
+        }
+        x += width;
     }
+    if row.get(x) == Some(&WIDE_TAIL) {
+        row[x] = ' ';
+    }
+}
+
+// 补空格到洞口宽度，保证整个洞口都被重写
+fn pad_hole_glyph(glyph: &str) -> String {
+    let padding = HOLE_WIDTH.saturating_sub(glyph.width());
+    format!("{}{}", glyph, " ".repeat(padding))
+}
+
+// 信息栏所在列，画面太窄时往左收，但不越过分隔线
+fn info_left(width: usize) -> usize {
+    (DIVIDER + INFO_GAP)
+        .min(width.saturating_sub(INFO_WIDTH))
+        .max(DIVIDER + 2)
+}
+
+// 补空格盖掉这一行上次留下的文字，写不下时截断，不覆盖最右侧的边框
+fn write_info(views: &mut Matrix<char>, row: usize, words: String) {
+    let width = views.first().map_or(0, |line| line.len());
+    let left = info_left(width);
+    let room = width.saturating_sub(left + 1);
+    let words = format!("{:<1$}", words, room);
+    write_words(views, left, row, words.chars().take(room).collect());
 }
 
 #[derive(Clone, Debug)]
@@ -69,13 +460,46 @@ struct Hole {
     y: usize,
 }
 
+// 一个洞的位置、出地鼠的权重和洞里的地鼠
+#[derive(Clone, Debug)]
+struct HoleState {
+    point: Hole,
+    rect: HoleRect, // 洞的边框，鼠标点在框里就算点中
+    weight: u32,
+    marmot: Marmot,
+    last_pressed: Option<Instant>, // 上一次按这个洞的时间，用来算冷却
+    squish: Option<(usize, Instant)>, // 压扁动画正在放的帧和这一帧开始的时间
+    escaped: Option<Instant>,      // 地鼠没被打中就缩回去了，洞口显示一会儿溜走的标记
+    hits: u32,                     // 本局在这个洞打中和打空的次数，结算时画热力图
+    misses: u32,
+    active: bool,                    // 专项练习时只有选中的洞出地鼠
+    best_reaction: Option<Duration>, // 本局在这个洞最快的一次反应
+}
+
 #[derive(Clone, Debug)]
 struct GameView {
     points: Matrix<usize>,
     views: Matrix<char>,
-    hole_points: Vec<Hole>,
-    hole_marmots: Vec<Marmot>,
+    holes: Vec<HoleState>,
     size: Dimension,
+    theme: Theme,
+    heat: Option<Color>,         // 连击时盖过主题的边框颜色
+    time_color: Option<Color>,   // 快没时间时盖过主题的倒计时颜色
+    status_color: Option<Color>, // 结算时按输赢给状态栏上色
+    combo_color: Option<Color>,  // 断了连击时连击行闪一下
+    empty_glyph: String,         // 空洞显示的字符
+    zen: bool,                   // 空洞是否播放待机动画
+    glyphs: Glyphs,
+    color: bool, // 终端不支持颜色时一律不上色
+    mini_map: bool,
+    mirror: bool,                // 信息栏画在棋盘左边
+    compact: bool,               // 只画一行：各洞排成一排，后面跟着得分、时间和状态
+    ghost: Vec<usize>,           // 最好那一局这时有地鼠的洞
+    highlight: bool,             // 有地鼠的洞边框换成主题的 occupied 颜色
+    numbered: bool,              // 顺序模式下地鼠显示成这一轮的编号
+    overlay: Option<[usize; 4]>, // 弹出的说明框的上下左右边，框外的内容都调暗
+    hidden_rows: Vec<usize>,     // 按 HUD 档位不显示的信息栏行
+    banner: Option<String>,      // 成就横幅，画的时候盖在棋盘上方那一行上，不改动画面
 }
 
 impl GameView {
@@ -83,14 +507,37 @@ impl GameView {
         GameView {
             points: vec![vec![0; size.width]; size.height],
             views: vec![vec![' '; size.width]; size.height],
-            hole_points: vec![],
-            hole_marmots: vec![],
+            holes: vec![],
             size: size.clone(),
+            theme: theme::find(DEFAULT_THEME).unwrap(),
+            heat: None,
+            time_color: None,
+            combo_color: None,
+            status_color: None,
+            empty_glyph: String::from(" "),
+            zen: false,
+            glyphs: EMOJI_GLYPHS,
+            color: true,
+            mini_map: false,
+            mirror: false,
+            compact: false,
+            ghost: vec![],
+            highlight: false,
+            numbered: false,
+            overlay: None,
+            hidden_rows: vec![],
+            banner: None,
         }
     }
 
     // 以左上角原点为基础构建游戏框架
-    fn build_block(&mut self, top: usize, bottom: usize, left: usize, right: usize) {
+    fn build_block(
+        &mut self,
+        top: usize,
+        bottom: usize,
+        left: usize,
+        right: usize,
+    ) -> Result<(), GameError> {
         let Dimension { width, height } = self.size;
         if top >= height
             || bottom >= height
@@ -99,8 +546,14 @@ impl GameView {
             || left == right
             || top == bottom
         {
-            eprintln!("\nCan not build the block! The parameters is incorrect!\nTraceBack:\n\tleft:{} right:{} width_limit:{}\n\ttop:{} bottom:{} height_limit:{}\n", left, right, width, top, bottom, height);
-            exit(0);
+            return Err(GameError::InvalidDimension {
+                top,
+                bottom,
+                left,
+                right,
+                width,
+                height,
+            });
         }
 
         for i in (top + 1)..bottom {
@@ -119,14 +572,360 @@ impl GameView {
         self.points[bottom][right] |= 9;
 
         self.views = self.update_block_char();
+        Ok(())
+    }
+
+    fn add_hole(&mut self, rect: &HoleRect) {
+        let mut marmot = Marmot::new();
+        marmot.view = self.glyphs.mole.to_string();
+        self.holes.push(HoleState {
+            point: Hole {
+                x: rect.x,
+                y: rect.y,
+            },
+            rect: rect.clone(),
+            weight: rect.weight,
+            marmot,
+            last_pressed: None,
+            squish: None,
+            escaped: None,
+            hits: 0,
+            misses: 0,
+            active: true,
+            best_reaction: None,
+        });
+    }
+
+    // 选出下一轮要出地鼠的洞，可能有重复
+    fn plan_spawn<R: Rng>(&self, rng: &mut R, max_moles: usize) -> Vec<usize> {
+        let random_num = get_random_num(rng, 1, max_moles);
+        let mut weights: Vec<u32> = self
+            .holes
+            .iter()
+            .map(|hole| if hole.active { hole.weight } else { 0 })
+            .collect();
+        // 选中的洞权重都是 0 时在这几个洞里均匀地选
+        if weights.iter().all(|&weight| weight == 0) {
+            weights = self
+                .holes
+                .iter()
+                .map(|hole| u32::from(hole.active))
+                .collect();
+        }
+        (0..random_num)
+            .map(|_| get_weighted_hole_idx(rng, &weights))
+            .collect()
+    }
+
+    // 最显眼的洞：能出地鼠的洞里权重最大的，一样大时取编号小的，默认布局里是正中间那个
+    fn clearest_hole(&self) -> Option<usize> {
+        self.holes
+            .iter()
+            .enumerate()
+            .filter(|(_, hole)| hole.active)
+            .max_by_key(|&(idx, hole)| (hole.weight, Reverse(idx)))
+            .map(|(idx, _)| idx)
+    }
+
+    // 每局第一轮照常随机挑，再把最显眼的洞排到第一个，只数不超过上限
+    fn plan_first_spawn<R: Rng>(&self, rng: &mut R, max_moles: usize) -> Vec<usize> {
+        let mut holes = self.plan_spawn(rng, max_moles);
+        if let Some(clearest) = self.clearest_hole() {
+            holes.retain(|&idx| idx != clearest);
+            holes.insert(0, clearest);
+            holes.truncate(max_moles.max(1));
+        }
+        holes
+    }
+
+    fn active_holes(&self) -> usize {
+        self.holes.iter().filter(|hole| hole.active).count()
+    }
+
+    fn write_hole(&mut self, idx: usize, glyph: &str) {
+        let Hole { x, y } = self.holes[idx].point;
+        write_words(&mut self.views, x, y, pad_hole_glyph(glyph));
+    }
+
+    // 空洞显示空洞字符，禅模式下显示动画的当前帧
+    fn write_idle(&mut self, idx: usize) {
+        let glyph = match self.holes[idx].marmot.idle_frame {
+            Some(frame) if self.zen => ZEN_FRAMES[frame % ZEN_FRAMES.len()].to_string(),
+            _ => self.empty_glyph.clone(),
+        };
+        self.write_hole(idx, &glyph);
+    }
+
+    // 所有空洞前进一帧，返回需要重画的行
+    fn animate_idle(&mut self) -> Vec<usize> {
+        let mut rows = vec![];
+        for idx in 0..self.holes.len() {
+            if let Some(frame) = self.holes[idx].marmot.idle_frame {
+                self.holes[idx].marmot.idle_frame = Some(frame + 1);
+                self.write_idle(idx);
+                rows.push(self.holes[idx].point.y);
+            }
+        }
+        rows.sort_unstable();
+        rows.dedup();
+        rows
+    }
+
+    fn start_squish(&mut self, idx: usize) {
+        self.holes[idx].squish = Some((0, Instant::now()));
+        self.write_hole(idx, self.glyphs.squish[0]);
+    }
+
+    // 到时间的压扁动画前进一帧，返回需要重画的行
+    fn animate_squish(&mut self) -> Vec<usize> {
+        let mut rows = vec![];
+        for idx in 0..self.holes.len() {
+            let Some((frame, since)) = self.holes[idx].squish else {
+                continue;
+            };
+            if since.elapsed() < SQUISH_FRAME {
+                continue;
+            }
+            if frame + 1 < self.glyphs.squish.len() {
+                self.holes[idx].squish = Some((frame + 1, Instant::now()));
+                self.write_hole(idx, self.glyphs.squish[frame + 1]);
+            } else {
+                self.holes[idx].squish = None;
+                self.holes[idx].marmot.idle_frame = Some(idx);
+                self.write_idle(idx);
+            }
+            rows.push(self.holes[idx].point.y);
+        }
+        rows
+    }
+
+    // 溜走的地鼠在洞口留下一个标记，这一轮又出了地鼠的洞不用
+    fn show_escapes(&mut self, escaped: &[usize]) {
+        for &idx in escaped {
+            if self.holes[idx].marmot.appeared {
+                continue;
+            }
+            self.holes[idx].escaped = Some(Instant::now());
+            self.holes[idx].marmot.idle_frame = None;
+            self.write_hole(idx, self.glyphs.escaped);
+        }
+    }
+
+    // 溜走的标记到时间就变回空洞，返回需要重画的行
+    fn animate_escapes(&mut self) -> Vec<usize> {
+        let mut rows = vec![];
+        for idx in 0..self.holes.len() {
+            if !matches!(self.holes[idx].escaped, Some(at) if at.elapsed() >= ESCAPE_TIME) {
+                continue;
+            }
+            self.holes[idx].escaped = None;
+            self.holes[idx].marmot.idle_frame = Some(idx);
+            self.write_idle(idx);
+            rows.push(self.holes[idx].point.y);
+        }
+        rows
+    }
+
+    // 每个洞按本局的命中率涂上深浅不同的方块，下方写上打中/打空的次数
+    fn show_heatmap(&mut self) {
+        for idx in 0..self.holes.len() {
+            let HoleState { hits, misses, .. } = self.holes[idx];
+            let attempts = hits + misses;
+            let glyph = if attempts == 0 {
+                String::new()
+            } else {
+                let level = (hits as usize * HEAT_LEVELS / attempts as usize).min(HEAT_LEVELS - 1);
+                self.glyphs.shades[level].to_string().repeat(HOLE_WIDTH)
+            };
+            self.holes[idx].marmot.idle_frame = None;
+            self.write_hole(idx, &glyph);
+            self.write_heat_label(idx, format!("{}/{}", hits, misses));
+        }
+    }
+
+    // 开新一局时先擦掉上一局的次数，再把计数清零
+    fn clear_heatmap(&mut self) {
+        for idx in 0..self.holes.len() {
+            let HoleState { hits, misses, .. } = self.holes[idx];
+            let width = format!("{}/{}", hits, misses).len();
+            self.write_heat_label(idx, " ".repeat(width));
+            self.holes[idx].hits = 0;
+            self.holes[idx].misses = 0;
+        }
     }
 
-    fn set_hole_points(&mut self, point: Hole) {
-        self.hole_points.push(point);
+    // 结算时在每个洞下方标出历来最快的反应时间，这一局破了纪录的加个 *
+    fn show_best_times(&mut self, best: &BestTimes, improved: &[usize]) {
+        for idx in 0..self.holes.len() {
+            let label = match best.get(idx) {
+                Some(time) if improved.contains(&idx) => format!("{}ms*", time.as_millis()),
+                Some(time) => format!("{}ms", time.as_millis()),
+                None => continue,
+            };
+            self.write_heat_label(idx, label);
+        }
+    }
+
+    // 开新一局时擦掉洞框里标纪录的那一行，再把本局的最快反应清空
+    fn clear_best_times(&mut self) {
+        for idx in 0..self.holes.len() {
+            let HoleRect {
+                left,
+                right,
+                bottom,
+                ..
+            } = self.holes[idx].rect;
+            let y = self.holes[idx].point.y + 1;
+            if y < bottom && left + 1 < right {
+                write_words(&mut self.views, left + 1, y, " ".repeat(right - left - 1));
+            }
+            self.holes[idx].best_reaction = None;
+        }
+    }
+
+    fn write_heat_label(&mut self, idx: usize, label: String) {
+        let Hole { x, y } = self.holes[idx].point;
+        let left = (x + HOLE_WIDTH / 2).saturating_sub(label.width().div_ceil(2));
+        write_words(&mut self.views, left, y + 1, label);
+    }
+
+    // 在即将出地鼠的空洞上显示提示
+    fn show_tells(&mut self, holes: &[usize]) {
+        for &idx in holes {
+            self.holes[idx].squish = None;
+            self.holes[idx].escaped = None;
+            let marmot = &mut self.holes[idx].marmot;
+            if !marmot.appeared {
+                marmot.idle_frame = None;
+                self.write_hole(idx, TELL_GLYPH);
+            }
+        }
+    }
+
+    // 清空所有洞，再让选中的洞出地鼠，返回没被打中就缩回去的洞
+    fn spawn(&mut self, holes: &[usize]) -> Vec<usize> {
+        self.spawn_with(holes, ClearStrategy::All, 0)
+    }
+
+    // 按清场方式撤掉该走的地鼠，没待满 min_rounds 轮的不撤，再让选中的空洞出地鼠，返回没被打中就缩回去的洞
+    fn spawn_with(&mut self, holes: &[usize], clear: ClearStrategy, min_rounds: u32) -> Vec<usize> {
+        let now = Instant::now();
+        let mut escaped = vec![];
+        for idx in 0..self.holes.len() {
+            let marmot = &mut self.holes[idx].marmot;
+            if marmot.appeared {
+                marmot.rounds += 1;
+                let stays = marmot.rounds < min_rounds
+                    || match clear {
+                        ClearStrategy::All => false,
+                        ClearStrategy::Expired => marmot.rounds < EXPIRE_ROUNDS,
+                        ClearStrategy::Never => true,
+                    };
+                if stays {
+                    continue;
+                }
+            }
+            self.holes[idx].squish = None;
+            self.holes[idx].escaped = None;
+            let marmot = &mut self.holes[idx].marmot;
+            if marmot.appeared {
+                marmot.left_at = Some(now);
+                escaped.push(idx);
+            }
+            marmot.appeared = false;
+            // 各洞的动画错开一帧，已经在转的接着转
+            marmot.idle_frame = Some(marmot.idle_frame.unwrap_or(idx));
+            self.write_idle(idx);
+        }
+        let mut order = 0;
+        for &idx in holes {
+            let HoleState { point, marmot, .. } = &mut self.holes[idx];
+            // 同一个洞选中两次也只出一只，编号按第一次算
+            if marmot.appeared {
+                continue;
+            }
+            order += 1;
+            marmot.appeared = true;
+            marmot.idle_frame = None;
+            marmot.left_at = None;
+            marmot.appeared_at = now;
+            marmot.order = order;
+            marmot.rounds = 0;
+            log_event!("spawn hole {}", idx + 1);
+            if !self.numbered {
+                write_words(
+                    &mut self.views,
+                    point.x,
+                    point.y,
+                    pad_hole_glyph(&marmot.view),
+                );
+            }
+        }
+        if self.numbered {
+            self.renumber();
+        }
+        self.write_mini_map();
+        escaped
+    }
+
+    // 顺序模式下按出来的先后给所有还在的地鼠重新编号，同一轮出来的按选中的先后
+    fn renumber(&mut self) {
+        let mut up: Vec<usize> = (0..self.holes.len())
+            .filter(|&idx| self.holes[idx].marmot.appeared)
+            .collect();
+        up.sort_by_key(|&idx| {
+            let marmot = &self.holes[idx].marmot;
+            (marmot.appeared_at, marmot.order)
+        });
+        for (order, idx) in (1..).zip(up) {
+            self.holes[idx].marmot.order = order;
+            self.write_hole(idx, &order.to_string());
+        }
+    }
+
+    // 打乱按键后在每只地鼠的上方标出对应的数字键
+    fn show_key_hints(&mut self, key_map: &[Vec<usize>]) {
+        for (digit, holes) in key_map.iter().enumerate() {
+            for &idx in holes.iter().filter(|&&idx| idx < self.holes.len()) {
+                let Hole { x, y } = self.holes[idx].point;
+                if let Some(row) = y.checked_sub(1) {
+                    write_words(&mut self.views, x, row, (digit + 1).to_string());
+                }
+            }
+        }
     }
 
-    fn set_hole_marmots(&mut self, marmot: Marmot) {
-        self.hole_marmots.push(marmot);
+    // 按数字键的顺序每三个洞一组
+    fn write_mini_map(&mut self) {
+        if !self.mini_map {
+            return;
+        }
+        let [up, down] = self.glyphs.map;
+        let mut map = String::from("Map:");
+        for (idx, hole) in self.holes.iter().enumerate() {
+            if idx % 3 == 0 {
+                map.push(' ');
+            }
+            map.push(if hole.marmot.appeared { up } else { down });
+        }
+        write_info(&mut self.views, MINI_MAP_ROW, map);
+    }
+
+    // 在地鼠上方写编号、下方写坐标，以地鼠为中心对齐
+    fn show_coordinates(&mut self) {
+        for (idx, HoleState { point: hole, .. }) in self.holes.iter().enumerate() {
+            let center = hole.x + HOLE_WIDTH / 2;
+            let labels = [
+                (hole.y.checked_sub(1), format!("#{}", idx + 1)),
+                (Some(hole.y + 1), format!("({},{})", hole.x, hole.y)),
+            ];
+            for (row, label) in labels {
+                if let Some(row) = row {
+                    let left = center.saturating_sub(label.width().div_ceil(2));
+                    write_words(&mut self.views, left, row, label);
+                }
+            }
+        }
     }
 
     fn update_block_char(&self) -> Matrix<char> {
@@ -135,37 +934,277 @@ impl GameView {
             char_vec.push(
                 point
                     .iter()
-                    .map(|&x| CHAR_VIEW_LIST[x])
+                    .map(|&x| self.glyphs.frame[x])
                     .collect::<Vec<char>>(),
             );
         }
         char_vec
     }
 
-    fn draw(&self) {
-        clear_terminal();
-        let mut styled_char_matrix = vec![];
-        for lines in &self.views {
-            let mut row = vec![];
-            for ch in lines {
-                row.push(style(ch))
+    // 把当前画面渲染成字符串，每行以换行分隔，不涉及终端
+    fn render_to_string(&self) -> String {
+        let mut frame = String::new();
+        for y in 0..self.views.len() {
+            frame.push_str(&self.render_row(y));
+            frame.push('\n');
+        }
+        frame
+    }
+
+    // 翻转时只是把信息栏和棋盘两块换个位置输出，各自内部的顺序不变
+    fn columns(&self, width: usize) -> Vec<usize> {
+        if !self.mirror || width <= DIVIDER + 1 {
+            return (0..width).collect();
+        }
+        let mut columns = vec![0];
+        columns.extend(DIVIDER + 1..width - 1);
+        columns.push(DIVIDER);
+        columns.extend(1..DIVIDER);
+        columns.push(width - 1);
+        columns
+    }
+
+    // 屏幕上的列对应画面里的哪一列，翻转时信息栏和棋盘换了位置
+    fn view_column(&self, column: usize) -> Option<usize> {
+        self.columns(self.views.first()?.len()).get(column).copied()
+    }
+
+    // 鼠标点中了哪个洞：默认要点在洞的边框以内，指定了半径时离洞口不超过这么多格就算，
+    // 同时落在几个洞的范围里时算最近的那个，哪个洞都不沾就返回 None
+    fn hole_at(&self, x: usize, y: usize, radius: Option<usize>) -> Option<usize> {
+        self.holes
+            .iter()
+            .enumerate()
+            .filter_map(|(idx, hole)| {
+                let Hole { x: left, y: top } = hole.point;
+                let right = left + HOLE_WIDTH - 1;
+                let dx = left.saturating_sub(x).max(x.saturating_sub(right));
+                let distance = dx.max(y.abs_diff(top));
+                let rect = &hole.rect;
+                let inside = match radius {
+                    Some(radius) => distance <= radius,
+                    None => {
+                        (rect.left..=rect.right).contains(&x)
+                            && (rect.top..=rect.bottom).contains(&y)
+                    }
+                };
+                inside.then_some((distance, idx))
+            })
+            .min()
+            .map(|(_, idx)| idx)
+    }
+
+    // 要画的一行：有成就横幅的那一行在拷贝上写上横幅，另外返回横幅占的列
+    fn row_cells(&self, y: usize) -> (Cow<'_, [char]>, Range<usize>) {
+        let Some(text) = self.banner.as_ref().filter(|_| y == ACHIEVEMENT_ROW) else {
+            return (Cow::Borrowed(&self.views[y]), 0..0);
+        };
+        let left = (DIVIDER + 1).saturating_sub(text.width()) / 2;
+        let mut line = vec![self.views[y].clone()];
+        write_words(&mut line, left, 0, text.clone());
+        let cells = line.pop().unwrap();
+        (Cow::Owned(cells), left..left + text.width())
+    }
+
+    // 开了高亮时这一行里有地鼠的洞框占的列
+    fn occupied_columns(&self, y: usize) -> Vec<RangeInclusive<usize>> {
+        if !self.highlight {
+            return vec![];
+        }
+        self.holes
+            .iter()
+            .filter(|hole| hole.marmot.appeared && (hole.rect.top..=hole.rect.bottom).contains(&y))
+            .map(|hole| hole.rect.left..=hole.rect.right)
+            .collect()
+    }
+
+    // 重影里有地鼠、眼下却空着的洞，返回这一格该画的重影字符
+    fn ghost_cell(&self, y: usize, x: usize) -> Option<char> {
+        let hole = self
+            .ghost
+            .iter()
+            .filter_map(|&idx| self.holes.get(idx))
+            .find(|hole| {
+                hole.point.y == y
+                    && (hole.point.x..hole.point.x + HOLE_WIDTH).contains(&x)
+                    && !hole.marmot.appeared
+                    && hole.squish.is_none()
+                    && hole.escaped.is_none()
+            })?;
+        self.glyphs.ghost.chars().nth(x - hole.point.x)
+    }
+
+    fn render_row(&self, y: usize) -> String {
+        let mut row = String::new();
+        let (cells, banner) = self.row_cells(y);
+        let occupied = self.occupied_columns(y);
+        let hidden = self.hidden_rows.contains(&y);
+        for x in self.columns(cells.len()) {
+            let ghost = (cells[x] == ' ').then(|| self.ghost_cell(y, x)).flatten();
+            // 藏起来的行只留边框，宽字符的两半都换成空格
+            let ch = if hidden && x > DIVIDER && self.points[y][x] == 0 {
+                ' '
+            } else {
+                ghost.unwrap_or(cells[x])
+            };
+            if ch == WIDE_TAIL {
+                continue;
+            }
+            let dimmed = ghost.is_some()
+                || matches!(self.overlay, Some([top, bottom, left, right])
+                if !(top..=bottom).contains(&y) || !(left..=right).contains(&x));
+            let color = if dimmed {
+                DIM_COLOR
+            } else if banner.contains(&x) {
+                WIN_COLOR
+            } else if self.points[y][x] != 0 && occupied.iter().any(|cols| cols.contains(&x)) {
+                self.theme.occupied
+            } else if self.points[y][x] != 0 {
+                self.heat.unwrap_or(self.theme.frame)
+            } else if x > DIVIDER {
+                self.info_color(y).unwrap_or(self.theme.text)
+            } else {
+                self.theme.text
+            };
+            row.push_str(&paint(ch, if self.color { color } else { Color::Reset }));
+        }
+        row
+    }
+
+    // 在棋盘中间弹出说明，画在一份拷贝上，关掉时重画原来的画面就行
+    fn with_overlay(&self, lines: &[String]) -> GameView {
+        let mut view = self.clone();
+        let width = lines.iter().map(|line| line.width()).max().unwrap_or(0) + 4;
+        let height = lines.len() + 2;
+        let left = (DIVIDER + 1).saturating_sub(width) / 2;
+        let top = self.size.height.saturating_sub(height) / 2;
+        let right = (left + width).min(self.size.width) - 1;
+        let bottom = (top + height).min(self.size.height) - 1;
+        for y in top..=bottom {
+            // 框边上压着半个宽字符时连另一半一起擦掉
+            if left > 0 && view.views[y][left] == WIDE_TAIL {
+                view.views[y][left - 1] = ' ';
+            }
+            if view.views[y].get(right + 1) == Some(&WIDE_TAIL) {
+                view.views[y][right + 1] = ' ';
             }
-            styled_char_matrix.push(row);
+            for x in left..=right {
+                view.views[y][x] = ' ';
+                view.points[y][x] = 0;
+            }
+        }
+        for (y, line) in (top + 1..bottom).zip(lines) {
+            write_words(&mut view.views, left + 2, y, line.clone());
+        }
+        view.overlay = Some([top, bottom, left, right]);
+        view
+    }
+
+    // 信息栏里个别行临时换色
+    fn info_color(&self, y: usize) -> Option<Color> {
+        match y {
+            TIME_ROW => self.time_color,
+            STATUS_ROW => self.status_color,
+            COMBO_ROW => self.combo_color,
+            _ => None,
+        }
+    }
+
+    // 信息栏里这一行的文字，宽字符的右半边照样占一格
+    fn info_cells(&self, y: usize) -> Vec<char> {
+        let row = &self.views[y];
+        let mut cells = row[info_left(row.len())..row.len().saturating_sub(1)].to_vec();
+        while cells.last() == Some(&' ') {
+            cells.pop();
         }
+        cells
+    }
 
-        for row in &styled_char_matrix {
-            for &ch in row {
-                print!("{}", ch);
+    // 精简模式下的那一行：洞里有东西时照画，空着的画成小地图上的空洞，补空格盖掉上次的内容
+    fn compact_line(&self) -> String {
+        let mut cells: Vec<(char, Color)> = vec![];
+        for hole in &self.holes {
+            let Hole { x, y } = hole.point;
+            let drawn = &self.views[y][x..x + HOLE_WIDTH];
+            if drawn.iter().all(|&ch| ch == ' ') {
+                cells.push((self.glyphs.map[1], self.theme.frame));
+                cells.push((' ', self.theme.text));
+            } else {
+                let color = if hole.marmot.appeared {
+                    self.theme.occupied
+                } else {
+                    self.theme.frame
+                };
+                cells.extend(drawn.iter().map(|&ch| (ch, color)));
             }
-            let _ = execute!(stdout(), MoveToNextLine(1));
+            cells.push((' ', self.theme.text));
+        }
+        for y in [SCORE_ROW, TIME_ROW, STATUS_ROW] {
+            let text = self.info_cells(y);
+            if text.is_empty() {
+                continue;
+            }
+            let color = self.info_color(y).unwrap_or(self.theme.text);
+            cells.push((' ', self.theme.text));
+            cells.extend(text.into_iter().map(|ch| (ch, color)));
+            cells.push((' ', self.theme.text));
+        }
+        let width = COMPACT_SIZE.width;
+        // 截断处压着半个宽字符时整个换成空格
+        if cells.get(width).is_some_and(|&(ch, _)| ch == WIDE_TAIL) {
+            cells[width - 1].0 = ' ';
+        }
+        cells.resize(width, (' ', self.theme.text));
+        cells
+            .into_iter()
+            .filter(|&(ch, _)| ch != WIDE_TAIL)
+            .map(|(ch, color)| paint(ch, if self.color { color } else { Color::Reset }))
+            .collect()
+    }
+
+    // 只重画指定的几行，整行输出不用去算宽字符的列偏移
+    fn draw_rows(&self, rows: &[usize]) {
+        let _ = self.draw_rows_to(&mut *output(), rows);
+    }
+
+    fn draw_rows_to(&self, out: &mut impl Write, rows: &[usize]) -> io::Result<()> {
+        if self.compact {
+            return self.draw_to(out);
+        }
+        for &y in rows {
+            queue!(out, MoveTo(0, y as u16))?;
+            write!(out, "{}", self.render_row(y))?;
+        }
+        out.flush()
+    }
+
+    fn draw(&self) {
+        let _ = self.draw_to(&mut *output());
+    }
+
+    fn draw_to(&self, out: &mut impl Write) -> io::Result<()> {
+        queue!(out, MoveTo(0, 0))?;
+        if self.compact {
+            write!(out, "{}", self.compact_line())?;
+            return out.flush();
+        }
+        for line in self.render_to_string().lines() {
+            write!(out, "{}", line)?;
+            queue!(out, MoveToNextLine(1))?;
         }
+        out.flush()
     }
 }
 
 #[derive(Clone, Debug)]
 struct Marmot {
     view: String,
-    appeared: bool, // 是否出现
+    appeared: bool,            // 是否出现
+    appeared_at: Instant,      // 最近一次出现的时间
+    idle_frame: Option<usize>, // 空洞动画的当前帧，洞里有地鼠、提示或打中标记时为 None
+    left_at: Option<Instant>,  // 没被打中就缩回去的时间，宽限期从这时算起
+    order: usize,              // 顺序模式下这一轮的编号，从 1 开始
+    rounds: u32,               // 出来以后已经过了几轮，只撤到期的地鼠时用
 }
 
 impl Marmot {
@@ -173,6 +1212,11 @@ impl Marmot {
         Marmot {
             view: String::from("🐭"),
             appeared: false,
+            appeared_at: Instant::now(),
+            idle_frame: None,
+            left_at: None,
+            order: 0,
+            rounds: 0,
         }
     }
 }
@@ -181,6 +1225,69 @@ impl Marmot {
 enum GameState {
     Stopped,
     Playing,
+    Overtime, // 加时赛：下一次打中就赢，打空就输
+}
+
+// 一局是怎么结束的，决定结算时显示什么
+#[derive(PartialEq, Clone, Copy, Debug)]
+enum EndReason {
+    Timeout,
+    TargetReached,
+    OvertimeWon,
+    OvertimeLost,
+    Milestone,  // 分数超过 1024 的彩蛋
+    OutOfLives, // 打开 --lives 时命用完了
+    Quit,
+}
+
+impl EndReason {
+    fn banner(&self) -> &'static str {
+        match self {
+            EndReason::Timeout | EndReason::OvertimeLost => "Game is Over!",
+            EndReason::TargetReached => "Target reached!",
+            EndReason::OvertimeWon => "You Win!",
+            EndReason::Milestone => "1024 cheers!",
+            EndReason::OutOfLives => "Out of lives!",
+            EndReason::Quit => "",
+        }
+    }
+
+    // 没有目标分数时时间到了不分输赢，返回 None
+    fn won(&self, has_target: bool) -> Option<bool> {
+        match self {
+            EndReason::TargetReached | EndReason::OvertimeWon | EndReason::Milestone => Some(true),
+            EndReason::OvertimeLost | EndReason::OutOfLives => Some(false),
+            EndReason::Timeout if has_target => Some(false),
+            EndReason::Timeout | EndReason::Quit => None,
+        }
+    }
+}
+
+// 同一次运行里多局游戏的累计成绩，重新开局时保留
+#[derive(Debug, Default)]
+struct SessionStats {
+    games: u32,
+    best_score: u128,
+    hits: u32,
+    misses: u32,
+}
+
+impl SessionStats {
+    fn record(&mut self, scores: u128, hits: u32, misses: u32) {
+        self.games += 1;
+        self.best_score = self.best_score.max(scores);
+        self.hits += hits;
+        self.misses += misses;
+    }
+
+    // 命中次数占全部出手的比例，还没出过手时按 0 算
+    fn accuracy(&self) -> f64 {
+        let attempts = self.hits + self.misses;
+        if attempts == 0 {
+            return 0.0;
+        }
+        f64::from(self.hits) / f64::from(attempts)
+    }
 }
 
 #[derive(Debug)]
@@ -189,6 +1296,41 @@ struct Game {
     state: GameState,
     scores: u128,
     time: u8,
+    options: Options,
+    started_at: Instant,
+    paused_at: Option<Instant>, // 这一次暂停是什么时候开始的
+    paused_for: Duration,       // 本局之前几次暂停一共停了多久
+    ended_at: Option<Instant>,  // 本局结束的时间，展示模式据此自动重开
+    spawn_not_before: Instant,  // 预热期和波间休息时只显示棋盘，不出地鼠
+    wave: u32,
+    lives: u32, // 剩下的命，没打开 --lives 时不用
+    hits: u32,
+    misses: u32,
+    combo: u32,                     // 连续命中的次数，打空或放跑地鼠就清零
+    combo_lost_at: Option<Instant>, // 刚断了连击，连击行还在显示提示
+    sound_enabled: bool,            // 局中可以按键静音，重开也保留
+    help_open: bool,                // 正显示着操作说明，更新循环不往屏幕上画
+    achievements: Achievements,
+    wave_clean: bool,                        // 这一波还没打空也没放跑过
+    next_order: usize,                       // 顺序模式下该打第几号了
+    banner_at: Option<Instant>,              // 成就横幅是什么时候弹出的
+    notice_at: Option<Instant>,              // 状态行上的临时提示是什么时候显示的
+    paused: bool,                            // 暂停时不计时、不出地鼠，按键也不算分
+    revealed: bool,                          // 作弊键让所有洞都出了地鼠，这时不再出新的
+    tick_elapsed: Duration,                  // 当前这一秒已经走过的时间，暂停时不算
+    since_hit: Duration,                     // 离上一次打中过了多久，暂停时不算
+    shown_time: u8,                          // 信息栏上显示的秒数，按 --timer 隔几秒才跟上实际时间
+    shown_scores: u128,                      // 信息栏上正在滚动的分数
+    next_spawn: Option<(Instant, Duration)>, // 下一次出地鼠的时间和出地鼠的间隔，节拍条用
+    rng: StdRng,
+    key_map: Vec<Vec<usize>>, // 第 n 个数字键对应的洞，改绑以后可以不止一个
+    reaction_total: Duration, // 本局所有命中的反应时间之和
+    ghost: Option<GhostRun>,  // 本次运行里得分最高的一局，打开 --ghost 时才记
+    recording: GhostRun,      // 正在记录的这一局
+    best_times: Option<BestTimes>, // 打开 --best-times 时各洞历来最快的反应时间
+    hit_limit: Option<HitLimit>, // 打开 --max-hits-per-second 时本局最近打中的时间
+    session: SessionStats,
+    generation: u32, // 每开一局加一，上一局的线程据此退出
 }
 
 impl Game {
@@ -197,184 +1339,2175 @@ impl Game {
             view: GameView::new(size),
             state: GameState::Stopped,
             scores: 0,
-            time: 60,
+            time: ROUND_TIME,
+            options: Options::default(),
+            started_at: Instant::now(),
+            paused_at: None,
+            paused_for: Duration::ZERO,
+            ended_at: None,
+            spawn_not_before: Instant::now(),
+            wave: 1,
+            lives: 0,
+            hits: 0,
+            misses: 0,
+            combo: 0,
+            combo_lost_at: None,
+            sound_enabled: false,
+            help_open: false,
+            achievements: Achievements::default(),
+            wave_clean: true,
+            next_order: 1,
+            banner_at: None,
+            notice_at: None,
+            paused: false,
+            revealed: false,
+            tick_elapsed: Duration::ZERO,
+            since_hit: Duration::ZERO,
+            next_spawn: None,
+            shown_time: 0,
+            shown_scores: 0,
+            rng: StdRng::from_entropy(),
+            key_map: vec![],
+            reaction_total: Duration::ZERO,
+            ghost: None,
+            recording: GhostRun::default(),
+            best_times: None,
+            hit_limit: None,
+            session: SessionStats::default(),
+            generation: 0,
         }
     }
-}
 
-fn main() -> Result<()> {
-    if let Err(_) = enable_raw_mode() {
-        eprintln!("Your terminal does not support raw mode!");
-        exit(0);
+    // 每秒得分，开局不足一秒时按 0 算，避免除以很小的数
+    fn score_rate(&self) -> f64 {
+        let elapsed = self.play_time().as_secs_f64();
+        if elapsed < 1.0 {
+            return 0.0;
+        }
+        self.scores as f64 / elapsed
     }
-    go_alternate_screen();
 
-    {
-        let _ = execute!(stdout(), SetTitle("打地鼠"));
-        let size = Dimension {
-            width: 70,
-            height: 25,
-        };
-        let mut game = GAME.lock().unwrap();
-        game.view.build_block(0, size.height - 1, 0, size.width - 1);
-        game.view.build_block(0, size.height - 1, 0, 40);
-
-        let initial_top = 3;
-        let initial_bottom = 7;
-        let initial_left = 3;
-        let initial_right = 11;
-        let horizontal_increment = 12;
-        let vertical_increment = 6;
-        for i in 0..=8 {
-            let horizontal_vector = i % 3;
-            let vertical_vector = i / 3;
-            let top = initial_top + vertical_increment * vertical_vector;
-            let bottom = initial_bottom + vertical_increment * vertical_vector;
-            let left = initial_left + horizontal_increment * horizontal_vector;
-            let right = initial_right + horizontal_increment * horizontal_vector;
-            game.view.build_block(top, bottom, left, right);
-            game.view.set_hole_points(Hole {
-                x: (left + right) / 2,
-                y: (top + bottom) / 2,
-            });
-            game.view.set_hole_marmots(Marmot::new());
-        }
-        game.state = GameState::Playing;
+    // 开局以来实际在玩的时间，暂停的那几段不算
+    fn play_time(&self) -> Duration {
+        let pausing = self.paused_at.map_or(Duration::ZERO, |at| at.elapsed());
+        self.started_at
+            .elapsed()
+            .saturating_sub(self.paused_for + pausing)
     }
 
-    fn start() {
+    // 开了按反应速度计分时按最快的那一档算，整行整列打的按同样的比例减半
+    fn speed_points(&self, reaction: Duration, points: u128) -> u128 {
+        match self
+            .options
+            .speed_points
+            .iter()
+            .find(|&&(within, _)| reaction <= within)
         {
-            let mut game = GAME.lock().unwrap();
-            let scores = game.scores;
-            let time = game.time;
-            write_words(&mut game.view.views, 50, 9, format!("Scores: {}", scores));
-            write_words(&mut game.view.views, 50, 11, format!("Time: {}", time));
-            write_words(&mut game.view.views, 50, 13, format!("q: {}", "quit the game"));
-            game.view.draw();
-        }
-
-        thread::spawn(|| loop {
-            let mut game = GAME.lock().unwrap();
-            if game.state == GameState::Stopped {
-                return;
-            }
-            std::thread::sleep(Duration::from_millis(1000));
-            let random_num = get_random_num(1, 6);
-            let mut marmots = game.view.hole_marmots.clone();
-            for idx in 0..9 {
-                marmots[idx].appeared = false;
-            }
-            for idx in 0..9 {
-                let points = &game.view.hole_points;
-                let point_x = points[idx].x;
-                let point_y = points[idx].y;
-                drop(points);
-                write_words(
-                    &mut game.view.views,
-                    point_x,
-                    point_y,
-                    String::from(" "),
-                );
-            }
-            for _ in 0..random_num {
-                let random_idx = get_random_num(0, 8);
-                let points = &game.view.hole_points;
-                let point_x = points[random_idx].x;
-                let point_y = points[random_idx].y;
-                drop(points);
-                marmots[random_idx].appeared = true;
-                write_words(
-                    &mut game.view.views,
-                    point_x,
-                    point_y,
-                    String::from("🐭"),
-                );
-            }
+            Some(&(_, tier)) => tier * points / self.options.points_per_hit,
+            None => points,
+        }
+    }
 
-            game.view.hole_marmots = marmots;
+    // 打中了就标记并加分，同一只地鼠只能得一次分
+    // 地鼠刚缩回去不久时打中算迟到，只得一部分分数
+    fn whack(&mut self, idx: usize, points: u128) -> bool {
+        if self.state == GameState::Stopped || self.paused {
+            return false;
+        }
+        let marmot = &self.view.holes[idx].marmot;
+        let late = !marmot.appeared
+            && matches!(marmot.left_at, Some(at) if at.elapsed() <= self.options.grace);
+        if !marmot.appeared && !late {
+            return false;
+        }
+        // 顺序模式下没按编号打的算打空，已经缩回去的不管顺序
+        if self.options.sequence && !late && marmot.order != self.next_order {
+            return false;
+        }
+        if let Some(limit) = &mut self.hit_limit {
+            if !limit.allow(Instant::now()) {
+                log_event!("hit hole {} ignored: over the hit limit", idx + 1);
+                return false;
+            }
+        }
+        let marmot = &self.view.holes[idx].marmot;
+        let points = self.speed_points(marmot.appeared_at.elapsed(), points);
+        let points = points * self.options.difficulty.points_factor;
+        let points = if late {
+            (points as f64 * self.options.late_points).round() as u128
+        } else {
+            points
+        };
+        self.view.holes[idx].escaped = None;
+        if self.options.squish {
+            self.view.start_squish(idx);
+        } else {
+            let hit = self.view.glyphs.hit;
+            self.view.write_hole(idx, hit);
+        }
+        let marmot = &mut self.view.holes[idx].marmot;
+        marmot.appeared = false;
+        marmot.idle_frame = None;
+        marmot.left_at = None;
+        let reaction = marmot.appeared_at.elapsed();
+        self.reaction_total += reaction;
+        let best = &mut self.view.holes[idx].best_reaction;
+        *best = Some(best.map_or(reaction, |best| best.min(reaction)));
+        self.view.write_mini_map();
+        self.scores += points;
+        self.hits += 1;
+        self.view.holes[idx].hits += 1;
+        self.write_stats();
+        self.since_hit = Duration::ZERO;
+        self.combo += 1;
+        self.combo_lost_at = None;
+        if !late {
+            self.next_order += 1;
+        }
+        let kind = if late { "late hit" } else { "hit" };
+        log_event!(
+            "{} hole {} +{} scores={}",
+            kind,
+            idx + 1,
+            points,
+            self.scores
+        );
+        let achievement = self.achievements.on_hit(self.combo, self.hits, self.misses);
+        self.show_achievement(achievement);
+        true
+    }
+
+    // 所有结束本局的情况都走这里，赢了和输了的结果用不同的颜色显示
+    fn end_game(&mut self, reason: EndReason) {
+        if self.state == GameState::Stopped {
+            return;
+        }
+        self.state = GameState::Stopped;
+        self.apply_hud();
+        log_event!("state Stopped: {:?}, scores={}", reason, self.scores);
+        self.notice_at = None;
+        self.ended_at = Some(Instant::now());
+        self.banner_at = None;
+        self.view.banner = None;
+        self.view.ghost.clear();
+        self.combo_lost_at = None;
+        self.update_combo();
+        // 更新循环马上要退出了，结算时直接显示最终分数
+        self.shown_scores = self.scores;
+        self.write_scores();
+        if reason == EndReason::Quit {
+            return;
+        }
+        self.view.status_color = match reason.won(self.options.target.is_some()) {
+            Some(true) => Some(WIN_COLOR),
+            Some(false) => Some(self.view.theme.warning),
+            None => None,
+        };
+        write_status(&mut self.view.views, reason.banner());
+        if self.options.heatmap {
+            self.view.show_heatmap();
+        }
+        // 中途退出的不算，打破纪录的这一局成为下一局的重影
+        if self.options.ghost
+            && self
+                .ghost
+                .as_ref()
+                .is_none_or(|ghost| self.scores > ghost.score)
+        {
+            self.recording.score = self.scores;
+            self.ghost = Some(mem::take(&mut self.recording));
+        }
+        if let Some(best) = &mut self.best_times {
+            let game: Vec<_> = self
+                .view
+                .holes
+                .iter()
+                .map(|hole| hole.best_reaction)
+                .collect();
+            let improved = best.merge(&game);
+            if let Some(path) = &self.options.best_times {
+                if let Err(err) = best.save(path) {
+                    log_event!("can not save best times to {}: {}", path.display(), err);
+                }
+            }
+            self.view.show_best_times(best, &improved);
+        }
+        self.session.record(self.scores, self.hits, self.misses);
+        self.write_summary();
+        self.write_time();
+    }
 
-            let scores = game.scores;
-            write_words(&mut game.view.views, 50, 9, format!("Scores: {}", scores));
+    // 保留棋盘、设置和累计成绩，重新开一局
+    fn restart(&mut self) {
+        self.generation += 1;
+        self.ended_at = None;
+        self.help_open = false;
+        self.scores = self.options.start_score;
+        self.shown_scores = self.scores;
+        self.hits = 0;
+        self.misses = 0;
+        self.combo = 0;
+        self.combo_lost_at = None;
+        self.update_combo();
+        self.reaction_total = Duration::ZERO;
+        self.wave = 1;
+        self.wave_clean = true;
+        self.next_order = 1;
+        self.achievements.reset();
+        self.banner_at = None;
+        self.view.banner = None;
+        self.recording = GhostRun::default();
+        self.lives = self.options.lives.unwrap_or(0);
+        self.hit_limit = self.options.max_hits_per_second.map(HitLimit::new);
+        self.paused = false;
+        self.revealed = false;
+        if self.options.heatmap {
+            self.view.clear_heatmap();
+        }
+        if self.best_times.is_some() {
+            self.view.clear_best_times();
+        }
+        self.shuffle_keys();
+        self.time = self.round_time();
+        self.tick_elapsed = Duration::ZERO;
+        self.since_hit = Duration::ZERO;
+        self.next_spawn = None;
+        self.view.spawn(&[]);
+        for hole in &mut self.view.holes {
+            hole.marmot.left_at = None;
+        }
+        self.view.status_color = None;
+        write_status(&mut self.view.views, "");
+        clear_summary(&mut self.view.views);
+        self.start_clock();
+        self.state = GameState::Playing;
+        self.apply_hud();
+        log_event!("state Playing");
+    }
 
-            game.view.draw();
-        });
+    // 从现在起算本局的时间和预热期
+    fn start_clock(&mut self) {
+        self.started_at = Instant::now();
+        self.paused_at = None;
+        self.paused_for = Duration::ZERO;
+        self.spawn_not_before = self.started_at + self.options.warm_up;
+    }
+
+    // 结算时在信息栏下方显示本局的反应时间和本次运行累计的成绩
+    fn write_summary(&mut self) {
+        let reaction = match self.average_reaction() {
+            Some(reaction) => format!("Reaction: {}ms", reaction.as_millis()),
+            None => String::from("Reaction: -"),
+        };
+        let session = &self.session;
+        let lines = [
+            reaction,
+            format!("Games: {}", session.games),
+            format!("Best: {}", session.best_score),
+            format!("Accuracy: {:.0}%", session.accuracy() * 100.0),
+            String::from("r/enter: restart"),
+            String::from("e: reset stats"),
+        ];
+        for (i, line) in lines.into_iter().enumerate() {
+            write_info(&mut self.view.views, SUMMARY_ROW + i, line);
+        }
+    }
+
+    // 本局从地鼠出现到被打中的平均用时，一只都没打中时为 None
+    fn average_reaction(&self) -> Option<Duration> {
+        if self.hits == 0 {
+            return None;
+        }
+        Some(self.reaction_total / self.hits)
+    }
+
+    fn reset_session(&mut self) {
+        self.session = SessionStats::default();
+        log_event!("session stats reset");
+        self.write_summary();
+    }
+
+    // 不打乱时数字键按顺序对应各个洞
+    fn shuffle_keys(&mut self) {
+        self.key_map = (0..self.view.holes.len()).map(|idx| vec![idx]).collect();
+        if self.options.shuffle_keys {
+            self.key_map.shuffle(&mut self.rng);
+        }
+        for (digit, holes) in &self.options.hole_keys {
+            if self.key_map.len() <= *digit {
+                self.key_map.resize(digit + 1, vec![]);
+            }
+            self.key_map[*digit] = holes.clone();
+        }
+        if self.options.shuffle_keys || !self.options.frame || !self.options.hole_keys.is_empty() {
+            self.view.show_key_hints(&self.key_map);
+        }
+    }
+
+    // 按数字键打它对应的所有洞
+    fn whack_key(&mut self, digit: usize, points: u128) {
+        if let Some(holes) = self.key_map.get(digit).cloned() {
+            self.whack_holes(&holes, points);
+        }
+    }
+
+    // 整行/整列按键一次打三个洞，每只只得一半分；单键模式下没有这种按键
+    fn whack_sweep(&mut self, key: char, points: u128) {
+        if self.options.single_key {
+            return;
+        }
+        let Some(holes) = get_sweep_holes(key) else {
+            return;
+        };
+        let count = self.view.holes.len();
+        let hits: Vec<usize> = holes
+            .into_iter()
+            .filter(|&idx| idx < count && self.press(idx) && self.whack(idx, points.div_ceil(2)))
+            .collect();
+        if !hits.is_empty() {
+            self.after_hits(&hits);
+        }
+    }
+
+    // 按数字键或者用鼠标点了洞，有地鼠的每个都打中、各自得分，一个都没打中才算打空一次
+    fn whack_holes(&mut self, holes: &[usize], points: u128) {
+        let count = self.view.holes.len();
+        let pressed: Vec<usize> = holes
+            .iter()
+            .copied()
+            .filter(|&idx| idx < count && self.press(idx))
+            .collect();
+        let dropped = self.dropped_hits();
+        let hits: Vec<usize> = pressed
+            .iter()
+            .copied()
+            .filter(|&idx| self.whack(idx, points))
+            .collect();
+        let Some(&first) = pressed.first() else {
+            return;
+        };
+        if !hits.is_empty() {
+            self.after_hits(&hits);
+        } else if self.dropped_hits() > dropped {
+            // 超过上限被忽略的那一下也不算打空
+        } else if self.on_miss(Some(first)) {
+            self.view.draw();
+        } else {
+            self.view.draw_rows(&[COMBO_ROW, STATS_ROW, STATS_ROW + 1]);
+        }
+    }
+
+    // 本局因为超过每秒上限被忽略的打中次数
+    fn dropped_hits(&self) -> u32 {
+        self.hit_limit.as_ref().map_or(0, |limit| limit.dropped)
+    }
+
+    // 冷却期内再按同一个洞直接忽略，既不算打中也不算打空
+    fn press(&mut self, idx: usize) -> bool {
+        let hole = &mut self.view.holes[idx];
+        if matches!(hole.last_pressed, Some(at) if at.elapsed() < self.options.cooldown) {
+            return false;
+        }
+        hole.last_pressed = Some(Instant::now());
+        true
+    }
+
+    // 暂停时清空棋盘，免得停下来慢慢看准了再打
+    fn toggle_pause(&mut self) {
+        if self.state == GameState::Stopped {
+            return;
+        }
+        self.paused = !self.paused;
+        self.notice_at = None;
+        if self.paused {
+            self.paused_at = Some(Instant::now());
+            self.view.spawn(&[]);
+            write_status(&mut self.view.views, "Paused");
+            log_event!("pause");
+        } else {
+            // 暂停时还在预热或者波间休息的话，停掉的这段不算休息，往后顺延
+            if let Some(at) = self.paused_at.take() {
+                let paused = at.elapsed();
+                self.paused_for += paused;
+                if self.spawn_not_before > at {
+                    self.spawn_not_before += paused;
+                }
+            }
+            write_status(&mut self.view.views, "");
+            log_event!("resume");
+        }
+    }
+
+    // 局中开关提示音，状态行上短暂提示一下，结束画面上只改设置
+    fn toggle_sound(&mut self) {
+        self.sound_enabled = !self.sound_enabled;
+        log_event!("sound {}", if self.sound_enabled { "on" } else { "off" });
+        if self.state == GameState::Stopped {
+            return;
+        }
+        let notice = if self.sound_enabled {
+            "Unmuted"
+        } else {
+            "Muted"
+        };
+        write_status(&mut self.view.views, notice);
+        self.notice_at = Some(Instant::now());
+    }
+
+    // 切到下一档 HUD，记在设置里，重开以后也照用
+    fn cycle_hud(&mut self) {
+        self.options.hud = self.options.hud.next();
+        log_event!("hud {}", self.options.hud.name());
+        self.apply_hud();
+        if self.state == GameState::Stopped {
+            return;
+        }
+        write_status(
+            &mut self.view.views,
+            &format!("HUD: {}", self.options.hud.name()),
+        );
+        self.notice_at = Some(Instant::now());
+    }
+
+    // 结算画面上的行和对局中的几行是同一块地方，结算时一律全显示
+    fn apply_hud(&mut self) {
+        self.view.hidden_rows = if self.state == GameState::Stopped {
+            vec![]
+        } else {
+            self.options.hud.hidden_rows()
+        };
+    }
+
+    // 详细档显示的本局统计，其他档也照样写，切过去就能看到
+    fn write_stats(&mut self) {
+        let reaction = match self.average_reaction() {
+            Some(reaction) => format!("Reaction: {}ms", reaction.as_millis()),
+            None => String::from("Reaction: -"),
+        };
+        let attempts = self.hits + self.misses;
+        let accuracy = if attempts == 0 {
+            0.0
+        } else {
+            f64::from(self.hits) / f64::from(attempts)
+        };
+        let lines = [
+            format!("Hits: {}  Misses: {}", self.hits, self.misses),
+            format!("Accuracy: {:.0}%", accuracy * 100.0),
+            reaction,
+        ];
+        for (i, line) in lines.into_iter().enumerate() {
+            write_info(&mut self.view.views, STATS_ROW + i, line);
+        }
+    }
+
+    // 临时提示到时间了就换回原来的状态，返回状态行是否要重画
+    fn expire_notice(&mut self) -> bool {
+        if !matches!(self.notice_at, Some(at) if at.elapsed() >= NOTICE_TIME) {
+            return false;
+        }
+        self.notice_at = None;
+        let status = if self.paused { "Paused" } else { "" };
+        write_status(&mut self.view.views, status);
+        true
+    }
+
+    // 调试用：所有洞同时出地鼠，好逐个检查数字键对应的洞，不消耗随机数
+    fn toggle_reveal(&mut self) {
+        if !self.options.cheats || self.state == GameState::Stopped {
+            return;
+        }
+        self.revealed = !self.revealed;
+        if self.revealed {
+            log_event!("reveal all");
+            let holes: Vec<usize> = (0..self.view.holes.len()).collect();
+            self.view.spawn(&holes);
+            self.next_order = 1;
+        } else {
+            log_event!("reveal off");
+            self.view.spawn(&[]);
+        }
+    }
+
+    // 刚结束的这一小会儿里按键都不算，免得本来要打地鼠的那一下把结算画面关掉或者直接重开
+    fn settling(&self) -> bool {
+        self.state == GameState::Stopped
+            && matches!(self.ended_at, Some(at) if at.elapsed() < self.options.end_delay)
+    }
+
+    // 过了 1024 分就结束本局、该弹彩蛋横幅了；无尽模式只在退出或者命用完时结束，不弹
+    fn reach_milestone(&mut self) -> bool {
+        if self.options.endless || self.scores <= 1024 {
+            return false;
+        }
+        self.end_game(EndReason::Milestone);
+        true
+    }
+
+    // 展示模式下结算画面停留够了就该自动开下一局
+    fn kiosk_due(&self) -> bool {
+        match (self.options.kiosk, self.ended_at) {
+            (Some(delay), Some(at)) => at.elapsed() >= delay,
+            _ => false,
+        }
+    }
+
+    // 本局结束或者已经开了新的一局，旧线程都该退出
+    fn is_over(&self, generation: u32) -> bool {
+        self.state == GameState::Stopped || self.generation != generation
+    }
+
+    fn on_hit(&mut self) {
+        if self.state == GameState::Overtime {
+            self.end_game(EndReason::OvertimeWon);
+        } else if matches!(self.options.target, Some(target) if self.scores >= target) {
+            self.end_game(EndReason::TargetReached);
+        }
+    }
+
+    // 返回是否需要整屏重画：本局因此结束，或者边框因断了连击而变色
+    fn on_miss(&mut self, idx: Option<usize>) -> bool {
+        if self.state == GameState::Stopped || self.paused {
+            return false;
+        }
+        self.misses += 1;
+        self.write_stats();
+        match idx {
+            Some(idx) => {
+                self.view.holes[idx].misses += 1;
+                log_event!("miss hole {}", idx + 1)
+            }
+            None => log_event!("miss"),
+        }
+        let cooled = self.break_combo();
+        if self.state != GameState::Overtime {
+            return cooled;
+        }
+        self.end_game(EndReason::OvertimeLost);
+        true
+    }
+
+    // 节拍条：离下一次出地鼠越近填得越满，出地鼠的那一刻清空
+    fn write_beat(&mut self) {
+        let [full, empty] = self.view.glyphs.map;
+        let filled = match self.next_spawn {
+            Some((at, interval)) if !interval.is_zero() => {
+                let until = at.saturating_duration_since(Instant::now());
+                let progress = 1.0 - (until.as_secs_f64() / interval.as_secs_f64()).min(1.0);
+                (progress * BEAT_STEPS as f64).ceil() as usize
+            }
+            _ => 0,
+        };
+        let bar: String = (0..BEAT_STEPS)
+            .map(|step| if step < filled { full } else { empty })
+            .collect();
+        write_info(&mut self.view.views, BEAT_ROW, format!("Beat: {}", bar));
+    }
+
+    // 太久没打中时每秒扣一点分，扣到 0 为止；预热和波间休息时不扣
+    fn decay(&mut self) {
+        let decay = self.options.decay;
+        if decay == 0
+            || self.since_hit < DECAY_IDLE
+            || Instant::now() < self.spawn_not_before
+            || self.scores == 0
+        {
+            return;
+        }
+        self.scores = self.scores.saturating_sub(decay);
+        log_event!("decay -{} scores={}", decay, self.scores);
+        self.write_scores();
+    }
+
+    fn on_time_up(&mut self) {
+        let close_to_target = matches!(
+            self.options.target,
+            Some(target) if self.scores < target
+                && target - self.scores <= OVERTIME_HITS * self.options.points_per_hit
+        );
+        if self.options.overtime && close_to_target {
+            self.state = GameState::Overtime;
+            log_event!("state Overtime, scores={}", self.scores);
+            write_status(&mut self.view.views, "Sudden death!");
+        } else if self.options.endless {
+            self.next_wave();
+        } else {
+            self.end_game(EndReason::Timeout);
+        }
+    }
+
+    // 进入下一波：清空洞口，休息一会儿再出地鼠
+    fn next_wave(&mut self) {
+        let achievement = self.achievements.on_wave(self.wave_clean);
+        self.show_achievement(achievement);
+        self.wave_clean = true;
+        self.wave += 1;
+        self.time = WAVE_TIME;
+        self.tick_elapsed = Duration::ZERO;
+        self.since_hit = Duration::ZERO;
+        self.spawn_not_before = Instant::now() + WAVE_BREAK;
+        self.view.spawn(&[]);
+        log_event!("wave {}, scores={}", self.wave, self.scores);
+        write_wave(&mut self.view.views, self.wave);
+        self.write_pause_banner(WAVE_BREAK);
+    }
+
+    // 每轮最多出几只：命令行指定的优先，否则按难度和能出地鼠的洞数算，都不超过这个洞数
+    fn max_moles(&self) -> usize {
+        let holes = self.view.active_holes();
+        self.options
+            .max_moles
+            .unwrap_or_else(|| self.options.difficulty.max_moles(holes))
+            .min(holes)
+    }
+
+    // 单键模式下场上只能有一只地鼠，不管 --clear 选了什么每轮都全部撤掉
+    fn clear_strategy(&self) -> ClearStrategy {
+        if self.options.single_key {
+            ClearStrategy::All
+        } else {
+            self.options.clear
+        }
+    }
+
+    // 出地鼠的间隔和每轮最多几只，无尽模式下每一波都更快、更多
+    fn spawn_params(&self) -> (Duration, usize) {
+        let difficulty = self.options.difficulty;
+        // 单键模式下一次只出一只，空格才知道该打哪一只
+        let cap = if self.options.single_key {
+            1
+        } else {
+            usize::MAX
+        };
+        if !self.options.endless {
+            return (difficulty.spawn_interval, self.max_moles().min(cap));
+        }
+        let faster = 0.85f64.powi(self.wave as i32 - 1);
+        let interval = difficulty
+            .spawn_interval
+            .mul_f64(faster)
+            .max(MIN_SPAWN_INTERVAL);
+        let max_moles = (self.max_moles() + self.wave as usize - 1)
+            .min(self.view.active_holes())
+            .min(cap);
+        (interval, max_moles)
+    }
+
+    fn write_pause_banner(&mut self, remaining: Duration) {
+        let secs = remaining.as_secs_f32().ceil() as u64;
+        let banner = if self.options.endless {
+            format!("Wave {} in {}", self.wave, secs)
+        } else {
+            format!("Ready: {}", secs)
+        };
+        write_status(&mut self.view.views, &banner);
+    }
+
+    // 打中后只重画得分行和被打中的洞所在的行，本局因此结束时才整屏重画
+    fn after_hits(&mut self, holes: &[usize]) {
+        self.on_hit();
+        self.write_scores();
+        let heated = self.update_combo();
+        if self.state == GameState::Stopped || heated {
+            self.view.draw();
+            return;
+        }
+        let mut rows: Vec<usize> = holes
+            .iter()
+            .map(|&idx| self.view.holes[idx].point.y)
+            .collect();
+        // 高亮的边框要跟着恢复原色
+        if self.view.highlight {
+            for &idx in holes {
+                let rect = &self.view.holes[idx].rect;
+                rows.extend(rect.top..=rect.bottom);
+            }
+        }
+        rows.push(SCORE_ROW);
+        rows.push(COMBO_ROW);
+        rows.push(MINI_MAP_ROW);
+        rows.extend(STATS_ROW..STATS_ROW + STATS_ROWS);
+        if self.view.banner.is_some() {
+            rows.push(ACHIEVEMENT_ROW);
+        }
+        if self.options.big_score {
+            rows.extend(BIG_SCORE_ROW..BIG_SCORE_ROW + BIG_ROWS);
+        }
+        rows.sort_unstable();
+        rows.dedup();
+        self.view.draw_rows(&rows);
+    }
+
+    // 打开 --lives 时每溜走一只少一条命，用完就结束
+    fn lose_lives(&mut self, escaped: usize) {
+        if self.options.lives.is_none() {
+            return;
+        }
+        self.lives = self.lives.saturating_sub(escaped as u32);
+        log_event!("lives {}", self.lives);
+        write_lives(&mut self.view.views, self.lives);
+        if self.lives == 0 {
+            self.end_game(EndReason::OutOfLives);
+        }
+    }
+
+    // 打空或者地鼠没打中就缩回去都会断连击，原本就是 0 时不算断
+    // 返回边框颜色是否变了
+    fn break_combo(&mut self) -> bool {
+        self.wave_clean = false;
+        if self.combo > 0 {
+            log_event!("combo lost at {}", self.combo);
+            self.combo_lost_at = Some(Instant::now());
+        }
+        self.combo = 0;
+        self.update_combo()
+    }
+
+    // 成就横幅在棋盘上方显示一会儿，时长为 0 时不显示
+    fn show_achievement(&mut self, achievement: Option<Achievement>) {
+        let Some(achievement) = achievement else {
+            return;
+        };
+        log_event!("achievement {:?}", achievement);
+        if self.options.achievement_time.is_zero() {
+            return;
+        }
+        self.view.banner = Some(achievement.banner().to_string());
+        self.banner_at = Some(Instant::now());
+    }
 
-        thread::spawn(|| loop {
-            std::thread::sleep(Duration::from_millis(1000));
-            let mut game = GAME.lock().unwrap();
+    // 成就横幅到时间了就撤掉，返回横幅那一行是否要重画
+    fn expire_achievement(&mut self) -> bool {
+        if !matches!(self.banner_at, Some(at) if at.elapsed() >= self.options.achievement_time) {
+            return false;
+        }
+        self.banner_at = None;
+        self.view.banner = None;
+        true
+    }
 
-            if game.state == GameState::Stopped {
+    // 断连击的提示到时间了就撤掉，返回连击行是否要重画
+    fn expire_combo_lost(&mut self) -> bool {
+        if !matches!(self.combo_lost_at, Some(at) if at.elapsed() >= COMBO_LOST_TIME) {
+            return false;
+        }
+        self.combo_lost_at = None;
+        self.update_combo();
+        true
+    }
+
+    // 刷新连击数和对应的边框颜色，返回边框颜色是否变了
+    fn update_combo(&mut self) -> bool {
+        let lost = self.combo_lost_at.is_some();
+        write_combo(&mut self.view.views, self.combo, lost);
+        self.view.combo_color = lost.then_some(self.view.theme.warning);
+        let heat = if self.options.heat {
+            theme::heat(self.combo)
+        } else {
+            None
+        };
+        let changed = heat != self.view.heat;
+        self.view.heat = heat;
+        changed
+    }
+
+    // 平时显示整秒，最后几秒显示到十分之一秒
+    fn write_time(&mut self) {
+        let exact = self.final_countdown() || self.state == GameState::Stopped;
+        match self.options.timer {
+            _ if exact => self.shown_time = self.time,
+            TimerDisplay::Every(step) => {
+                if self.time > self.shown_time || self.shown_time - self.time >= step {
+                    self.shown_time = self.time;
+                }
+            }
+            TimerDisplay::Final => {
+                if self.options.big_score {
+                    for row in BIG_TIME_ROW..BIG_TIME_ROW + BIG_ROWS {
+                        write_info(&mut self.view.views, row, String::new());
+                    }
+                }
+                write_info(&mut self.view.views, TIME_ROW, String::new());
                 return;
             }
+        }
+        let remaining = self.remaining();
+        self.view.time_color =
+            if self.warning() && (!self.options.flash || remaining.as_millis() / 500 % 2 == 1) {
+                Some(self.view.theme.warning)
+            } else {
+                None
+            };
+        let text = if remaining < Duration::from_secs(FINE_TIME.into()) {
+            let tenths = remaining.as_millis() / 100;
+            format!("Time: {}.{}", tenths / 10, tenths % 10)
+        } else {
+            format!("Time: {}", self.shown_time)
+        };
+        if self.options.big_score {
+            write_big(&mut self.view.views, BIG_TIME_ROW, &text["Time: ".len()..]);
+        }
+        write_info(&mut self.view.views, TIME_ROW, text);
+    }
+
+    // 开了滚动计分时显示的是正在追赶实际分数的那个数
+    fn write_scores(&mut self) {
+        let scores = if self.options.count_up {
+            self.shown_scores
+        } else {
+            self.scores
+        };
+        write_scores(&mut self.view.views, scores);
+        if self.options.big_score {
+            write_big(&mut self.view.views, BIG_SCORE_ROW, &scores.to_string());
+        }
+    }
+
+    // 显示的分数向实际分数走一步，差得越多走得越快，返回需要重画的行
+    fn count_up(&mut self) -> Vec<usize> {
+        if self.shown_scores == self.scores {
+            return vec![];
+        }
+        let step = self
+            .scores
+            .abs_diff(self.shown_scores)
+            .div_ceil(COUNT_UP_STEPS);
+        if self.shown_scores < self.scores {
+            self.shown_scores += step;
+        } else {
+            self.shown_scores -= step;
+        }
+        self.write_scores();
+        let mut rows = vec![SCORE_ROW];
+        if self.options.big_score {
+            rows.extend(BIG_SCORE_ROW..BIG_SCORE_ROW + BIG_ROWS);
+        }
+        rows
+    }
+
+    fn remaining(&self) -> Duration {
+        Duration::from_secs(self.time.into()).saturating_sub(self.tick_elapsed)
+    }
+
+    // 按本局的时钟回放最好那一局，重影换了一轮时返回 true
+    fn replay_ghost(&mut self, clock: u64) -> bool {
+        if self.state == GameState::Stopped {
+            return false;
+        }
+        let Some((holes, score)) = self.ghost.as_ref().and_then(|ghost| ghost.at(clock)) else {
+            return false;
+        };
+        if self.view.ghost == holes {
+            return false;
+        }
+        self.view.ghost = holes.to_vec();
+        write_ghost(&mut self.view.views, score, self.scores);
+        true
+    }
+
+    // 最后几秒，倒计时不论 --timer 怎么设都逐帧刷新
+    fn final_countdown(&self) -> bool {
+        self.time <= FINE_TIME || self.warning()
+    }
+
+    // 结束后不再提醒
+    fn warning(&self) -> bool {
+        self.state != GameState::Stopped
+            && self.remaining() < Duration::from_secs(self.options.warn_time)
+    }
+
+    // 本局已进行的比例，0 为刚开局，1 为时间到
+    fn round_fraction(&self) -> f64 {
+        let round_time = self.round_time();
+        f64::from(round_time - self.time) / f64::from(round_time)
+    }
+
+    // 无尽模式下每一波各自计时
+    fn round_time(&self) -> u8 {
+        if self.options.endless {
+            WAVE_TIME
+        } else {
+            self.options.difficulty.round_time
+        }
+    }
+}
+
+// 一帧里各步更新要重画的内容，这一帧的更新都做完再统一输出
+#[derive(Default)]
+struct Frame {
+    rows: Vec<usize>,
+    full: bool, // 整屏重画，这时不用再管 rows
+}
+
+impl Frame {
+    fn draw(mut self, view: &GameView) {
+        if self.full {
+            view.draw();
+        } else if !self.rows.is_empty() {
+            self.rows.sort_unstable();
+            self.rows.dedup();
+            view.draw_rows(&self.rows);
+        }
+    }
+}
+
+// 更新循环的状态，每一步都只前进 UPDATE_STEP，不看这一步实际花了多久
+struct Updater {
+    generation: u32,
+    lead: Duration,              // 提示提前量，开局时读取一次，提示关闭时为 0
+    params: (Duration, usize),   // 这一轮出地鼠的间隔和最多几只
+    spawn_elapsed: Duration,     // 这一轮已经走过的时间，暂停时不走
+    planned: Option<Vec<usize>>, // 已经给出提示、到时间就出地鼠的洞
+    waiting: bool,               // 状态行上正显示着预热或波间休息的倒计时
+    effects: bool,               // 开了任何一种动画
+    zen: bool,
+    effect_elapsed: Duration,
+    zen_elapsed: Duration,
+    slowest: Duration, // 最慢的一步花了多久，结束时写进日志
+    clock: u64,        // 开局以来走过的步数，暂停时不走，记录和回放重影都按它对齐
+    first_round: bool, // 这一局还没挑过洞
+}
 
-            if game.time > 0 {
-                game.time -= 1;
+impl Updater {
+    fn new(game: &Game) -> Self {
+        let options = &game.options;
+        Updater {
+            generation: game.generation,
+            lead: options.tell,
+            params: game.spawn_params(),
+            spawn_elapsed: Duration::ZERO,
+            planned: None,
+            waiting: true,
+            effects: options.zen || options.squish || options.escapes || options.count_up,
+            zen: options.zen,
+            effect_elapsed: Duration::ZERO,
+            zen_elapsed: Duration::ZERO,
+            slowest: Duration::ZERO,
+            clock: 0,
+            first_round: true,
+        }
+    }
+
+    fn step(&mut self, game: &mut Game, frame: &mut Frame) {
+        let started_at = Instant::now();
+        if !game.paused {
+            self.clock += 1;
+        }
+        self.spawn(game, frame);
+        self.tick(game, frame);
+        self.animate(game, frame);
+        if game.replay_ghost(self.clock) {
+            frame.full = true;
+        }
+        self.slowest = self.slowest.max(started_at.elapsed());
+    }
+
+    // 一轮快结束时先挑好洞给出提示，到点再出地鼠
+    fn spawn(&mut self, game: &mut Game, frame: &mut Frame) {
+        if game.paused || game.revealed {
+            return;
+        }
+        let now = Instant::now();
+        if now + self.lead < game.spawn_not_before {
+            // 预热和波间休息结束的那一刻正好出第一轮
+            self.waiting = true;
+            self.planned = None;
+            self.spawn_elapsed = self.params.0.saturating_sub(self.lead);
+            let remaining = game.spawn_not_before - now;
+            game.write_pause_banner(remaining);
+            frame.rows.push(STATUS_ROW);
+            return;
+        }
+        self.spawn_elapsed += UPDATE_STEP;
+        if self.planned.is_none() && self.spawn_elapsed + self.lead >= self.params.0 {
+            self.params = game.spawn_params();
+            let max_moles = game.options.curve.cap(game.round_fraction(), self.params.1);
+            let holes = if self.first_round && game.options.first_mole {
+                game.view.plan_first_spawn(&mut game.rng, max_moles)
             } else {
-                game.state = GameState::Stopped;
-                write_words(&mut game.view.views, 50, 5, format!("Game is Over!"));
+                game.view.plan_spawn(&mut game.rng, max_moles)
+            };
+            self.first_round = false;
+            if !self.lead.is_zero() {
+                game.view.show_tells(&holes);
+                frame.full = true;
+            }
+            self.planned = Some(holes);
+        }
+        if self.spawn_elapsed < self.params.0 {
+            return;
+        }
+        let Some(holes) = self.planned.take() else {
+            return;
+        };
+        self.spawn_elapsed -= self.params.0;
+        if self.waiting {
+            self.waiting = false;
+            write_status(&mut game.view.views, "");
+        }
+        let interval = self.params.0;
+        // 没待够最短时间的地鼠这一轮不撤，按轮数算，和更新循环一样不看实际过了多久
+        let min_rounds = game
+            .options
+            .min_visible
+            .as_nanos()
+            .div_ceil(interval.as_nanos().max(1));
+        let escaped = game
+            .view
+            .spawn_with(&holes, game.clear_strategy(), min_rounds as u32);
+        game.next_order = 1;
+        game.next_spawn = Some((Instant::now() + interval, interval));
+        if game.options.ghost {
+            let holes = (0..game.view.holes.len())
+                .filter(|&idx| game.view.holes[idx].marmot.appeared)
+                .collect();
+            game.recording.record(self.clock, holes, game.scores);
+        }
+        if !escaped.is_empty() {
+            game.break_combo();
+            if game.options.escapes {
+                game.view.show_escapes(&escaped);
             }
+            if game.sound_enabled {
+                beep();
+            }
+            // 命用完时结算画面要盖在溜走的标记上面
+            game.lose_lives(escaped.len());
+        }
+        game.write_scores();
+        frame.full = true;
+    }
+
+    // 倒计时，暂停的这段不算
+    fn tick(&mut self, game: &mut Game, frame: &mut Frame) {
+        if game.expire_combo_lost() {
+            frame.rows.push(COMBO_ROW);
+        }
+        if game.expire_notice() {
+            frame.rows.push(STATUS_ROW);
+        }
+        if game.expire_achievement() {
+            frame.rows.push(ACHIEVEMENT_ROW);
+        }
+        if game.paused || game.time == 0 {
+            return;
+        }
+        game.tick_elapsed += UPDATE_STEP;
+        game.since_hit += UPDATE_STEP;
+        if game.options.beat {
+            game.write_beat();
+            frame.rows.push(BEAT_ROW);
+        }
+        if game.tick_elapsed < Duration::from_secs(1) {
+            if game.final_countdown() {
+                game.write_time();
+                frame.rows.push(TIME_ROW);
+                if game.options.big_score {
+                    frame.rows.extend(BIG_TIME_ROW..BIG_TIME_ROW + BIG_ROWS);
+                }
+            }
+            return;
+        }
+        game.tick_elapsed -= Duration::from_secs(1);
+        game.time -= 1;
+        game.decay();
+        if game.time == 0 {
+            game.tick_elapsed = Duration::ZERO;
+            game.on_time_up();
+        }
 
-            let time = game.time;
-            write_words(&mut game.view.views, 50, 11, format!("Time: {}", time));
+        let rate = game.score_rate();
+        write_rate(&mut game.view.views, rate);
+        game.write_time();
+        frame.full = true;
+    }
 
-            game.view.draw();
-        });
+    // 各个洞的动画各自计时，互不等待
+    fn animate(&mut self, game: &mut Game, frame: &mut Frame) {
+        if !self.effects || game.paused {
+            return;
+        }
+        self.effect_elapsed += UPDATE_STEP;
+        if self.effect_elapsed < ANIMATION_TICK {
+            return;
+        }
+        self.effect_elapsed -= ANIMATION_TICK;
+        frame.rows.extend(game.view.animate_squish());
+        frame.rows.extend(game.view.animate_escapes());
+        frame.rows.extend(game.count_up());
+        self.zen_elapsed += ANIMATION_TICK;
+        if self.zen && self.zen_elapsed >= ZEN_TICK {
+            self.zen_elapsed -= ZEN_TICK;
+            frame.rows.extend(game.view.animate_idle());
+        }
+    }
+}
+
+// 说明框里列出的所有操作，跟着按键绑定和打开的选项变
+fn help_lines(options: &Options) -> Vec<String> {
+    let keys = options.keys;
+    let mut lines = vec![String::from("Controls"), String::new()];
+    if options.single_key {
+        lines.push(String::from("space: whack the mole"));
+    } else {
+        lines.push(String::from("1-9: whack a hole"));
+        lines.push(String::from("asd: whack a row"));
+        lines.push(String::from("zxc: whack a column"));
+    }
+    if options.mouse {
+        lines.push(String::from("click: whack a hole"));
+    }
+    lines.push(format!("{}: pause", keys.pause));
+    lines.push(format!("{}: mute", keys.mute));
+    lines.push(format!("{}: cycle the HUD", keys.hud));
+    lines.push(format!("{}: quit", keys.quit));
+    lines.push(String::from("r or enter: play again"));
+    lines.push(String::from("e: reset the session"));
+    lines.push(format!("{} or h: this help", keys.help));
+    lines.push(String::new());
+    lines.push(String::from("Press any key to close"));
+    lines
+}
+
+fn write_scores(views: &mut Matrix<char>, scores: u128) {
+    write_info(views, SCORE_ROW, format!("Scores: {}", scores));
+}
+
+fn write_combo(views: &mut Matrix<char>, combo: u32, lost: bool) {
+    let text = if lost {
+        format!("Combo: {} COMBO LOST", combo)
+    } else {
+        format!("Combo: {}", combo)
+    };
+    write_info(views, COMBO_ROW, text);
+}
+
+fn write_big(views: &mut Matrix<char>, top: usize, text: &str) {
+    for (row, line) in (top..).zip(big_digits::render(text)) {
+        write_info(views, row, line);
+    }
+}
+
+// 重影在同一时刻的得分，括号里是领先或落后多少
+fn write_ghost(views: &mut Matrix<char>, ghost: u128, scores: u128) {
+    let lead = scores as i128 - ghost as i128;
+    write_info(views, GHOST_ROW, format!("Ghost: {} ({:+})", ghost, lead));
+}
+
+fn write_lives(views: &mut Matrix<char>, lives: u32) {
+    write_info(views, LIVES_ROW, format!("Lives: {}", lives));
+}
+
+fn write_target(views: &mut Matrix<char>, target: u128) {
+    write_info(views, TARGET_ROW, format!("Target: {}", target));
+}
+
+fn write_rate(views: &mut Matrix<char>, rate: f64) {
+    write_info(
+        views,
+        RATE_ROW,
+        format!("{:<16}", format!("Rate: {:.1}/s", rate)),
+    );
+}
+
+fn write_wave(views: &mut Matrix<char>, wave: u32) {
+    write_info(views, WAVE_ROW, format!("Wave: {}", wave));
+}
+
+fn write_status(views: &mut Matrix<char>, banner: &str) {
+    write_info(views, STATUS_ROW, banner.to_string());
+}
+
+fn clear_summary(views: &mut Matrix<char>) {
+    for row in SUMMARY_ROW..SUMMARY_ROW + SUMMARY_ROWS {
+        write_info(views, row, String::new());
+    }
+}
+
+// 出错时已经进了 raw 模式的先把终端恢复，错误信息才看得见
+fn main() -> ExitCode {
+    match try_main() {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(err) => {
+            if is_raw_mode_enabled().unwrap_or(false) {
+                let _ = teardown_terminal();
+            }
+            eprintln!("{}", err);
+            ExitCode::from(err.exit_code())
+        }
+    }
+}
+
+// 参数不是合法的 UTF-8 时 env::args 会直接 panic，这里改成报错
+fn utf8_args() -> Result<Vec<String>, GameError> {
+    env::args_os()
+        .skip(1)
+        .map(|arg| {
+            arg.into_string().map_err(|arg| {
+                GameError::ConfigParse(format!(
+                    "Argument is not valid UTF-8: {}",
+                    arg.to_string_lossy()
+                ))
+            })
+        })
+        .collect()
+}
+
+fn try_main() -> Result<(), GameError> {
+    let args = utf8_args()?;
+    #[cfg(feature = "render-bench")]
+    if args.first().map(String::as_str) == Some("--bench-render") {
+        bench::run();
+        return Ok(());
+    }
+    // 隐藏命令：--soak 帧数 [种子]，不开终端让演示玩家连着玩
+    if args.first().map(String::as_str) == Some("--soak") {
+        let mut args = args.iter().skip(1);
+        let frames = args.next().and_then(|frames| frames.parse().ok());
+        let seed = args.next().map_or(Some(0), |seed| seed.parse().ok());
+        return match (frames, seed) {
+            (Some(frames), Some(seed)) => soak::run(frames, seed),
+            _ => Err(GameError::ConfigParse(String::from(
+                "Usage: --soak <frames> [seed]",
+            ))),
+        };
+    }
+    let options = Options::parse(args.into_iter()).map_err(GameError::ConfigParse)?;
+    if options.list_themes {
+        for theme in THEMES {
+            let default = if theme.name == DEFAULT_THEME {
+                " (default)"
+            } else {
+                ""
+            };
+            println!("{}{}", theme.name, default);
+        }
+        return Ok(());
+    }
+    if options.list_difficulties {
+        for difficulty in DIFFICULTIES {
+            let default = if difficulty.name == DEFAULT_DIFFICULTY {
+                " (default)"
+            } else {
+                ""
+            };
+            // 和普通难度玩法不同的才标出局长和得分倍数
+            let details = if difficulty.round_time != ROUND_TIME || difficulty.points_factor != 1 {
+                format!(
+                    " ({}s rounds, {}x points)",
+                    difficulty.round_time, difficulty.points_factor
+                )
+            } else {
+                String::new()
+            };
+            println!("{}{}{}", difficulty.name, default, details);
+        }
+        return Ok(());
+    }
+    if let Some(path) = &options.log {
+        event_log::open(path).map_err(|source| GameError::LogFile {
+            path: path.clone(),
+            source,
+        })?;
+    }
+    let layout = match &options.layout {
+        Some(path) => Layout::load(path),
+        None => Layout::grid(options.gap),
+    };
+    let layout = layout.and_then(|mut layout| {
+        if options.mirror {
+            layout.mirror()?;
+        }
+        Ok(layout)
+    });
+    let layout = layout
+        .and_then(|layout| layout.validate(&BOARD_SIZE).map(|()| layout))
+        .map_err(GameError::InvalidLayout)?;
+    if let Some(&idx) = options
+        .active_holes
+        .iter()
+        .flatten()
+        .find(|&&idx| idx >= layout.holes.len())
+    {
+        return Err(GameError::ConfigParse(format!(
+            "--active-holes: there is no hole {}, the layout has {}",
+            idx + 1,
+            layout.holes.len()
+        )));
+    }
+    let best_times = options
+        .best_times
+        .as_deref()
+        .map(BestTimes::load)
+        .transpose()
+        .map_err(GameError::ConfigParse)?;
+    let caps = setup_terminal(&options)?;
+    let game = build_game(options, &layout, &caps, best_times)?;
+    *lock_game() = game;
+    run()?;
+    teardown_terminal().map_err(GameError::Output)
+}
+
+// 检查终端、打开 raw 模式和备用屏幕，返回按选项修正过的终端能力
+fn setup_terminal(options: &Options) -> Result<Capabilities, GameError> {
+    if !stdin().is_terminal() || !stdout().is_terminal() {
+        return Err(GameError::NotATerminal);
+    }
+    // 大小未知时照常开始
+    let needed = if options.compact {
+        COMPACT_SIZE
+    } else {
+        BOARD_SIZE
+    };
+    if let Some(size) = plausible_size(terminal::size()) {
+        if size.width < needed.width || size.height < needed.height {
+            return Err(GameError::TerminalTooSmall {
+                columns: size.width,
+                rows: size.height,
+                width: needed.width,
+                height: needed.height,
+            });
+        }
+    }
+    let mut caps = Capabilities::detect();
+    caps.unicode = options.unicode.unwrap_or(caps.unicode);
+    caps.emoji = options.unicode.unwrap_or(caps.emoji);
+    caps.color = options.color.unwrap_or(caps.color);
+    caps.alt_screen = options.alt_screen.unwrap_or(caps.alt_screen);
+    ALT_SCREEN.store(caps.alt_screen, Ordering::Relaxed);
+    MOUSE.store(options.mouse, Ordering::Relaxed);
+    if enable_raw_mode().is_err() {
+        return Err(GameError::RawModeUnsupported);
+    }
+    go_alternate_screen(&mut *output()).map_err(GameError::Output)?;
+    // 主线程 panic 时先恢复终端再打印错误，其他线程 panic 后游戏照常进行
+    // panic 时可能正拿着输出的锁，这里直接写终端
+    let default_hook = panic::take_hook();
+    panic::set_hook(Box::new(move |info| {
+        if thread::current().name() == Some("main") {
+            let _ = leave_alternate_screen(&mut stdout());
+            let _ = disable_raw_mode();
+        }
+        default_hook(info);
+    }));
+
+    let title = match &options.title {
+        Some(title) => title.clone(),
+        None => options
+            .lang
+            .unwrap_or_else(Lang::detect)
+            .title()
+            .to_string(),
+    };
+    {
+        let mut out = output();
+        let _ = execute!(out, SetTitle(title));
+    }
+    Ok(caps)
+}
+
+// 按选项和布局搭好棋盘，不碰终端，也不动全局的那一局
+fn build_game(
+    options: Options,
+    layout: &Layout,
+    caps: &Capabilities,
+    best_times: Option<BestTimes>,
+) -> Result<Game, GameError> {
+    let size = BOARD_SIZE;
+    let mut game = Game::new(&size);
+    game.view.glyphs = Glyphs::for_capabilities(caps).themed(&options.theme.glyphs, caps.emoji);
+    if let Some(border) = options.border {
+        game.view.glyphs.frame = border.frame();
+    }
+    game.view.color = caps.color;
+    // 不画边框时只剩地鼠和洞上方的数字键提示
+    if options.frame {
+        game.view
+            .build_block(0, size.height - 1, 0, size.width - 1)?;
+        game.view.build_block(0, size.height - 1, 0, DIVIDER)?;
+    }
+
+    for hole in &layout.holes {
+        if options.frame {
+            game.view
+                .build_block(hole.top, hole.bottom, hole.left, hole.right)?;
+        }
+        game.view.add_hole(hole);
+    }
+    if let Some(active) = &options.active_holes {
+        for (idx, hole) in game.view.holes.iter_mut().enumerate() {
+            hole.active = active.contains(&idx);
+        }
+    }
+    game.view.theme = options.theme;
+    game.view.empty_glyph = options
+        .empty_glyph
+        .clone()
+        .unwrap_or_else(|| game.view.glyphs.empty.to_string());
+    game.view.zen = options.zen;
+    game.view.mini_map = options.mini_map;
+    game.view.mirror = options.mirror;
+    game.view.compact = options.compact;
+    game.view.numbered = options.sequence;
+    game.best_times = best_times;
+    game.view.highlight = options.highlight;
+    if let Some(seed) = options.seed {
+        game.rng = StdRng::seed_from_u64(seed);
+    }
+    if options.debug_layout {
+        game.view.show_coordinates();
+    }
+    game.options = options;
+    // 盲文点阵的大号数字需要 Unicode
+    game.options.big_score &= caps.unicode;
+    game.sound_enabled = game.options.sound;
+    game.restart();
+    Ok(game)
+}
+
+// 每局开始时写好信息栏、放开场动画，再起一个更新线程推进这一局
+fn start() {
+    {
+        let mut game = lock_game();
+        let keys = game.options.keys;
+        let single_key = game.options.single_key;
+        game.write_scores();
+        game.write_stats();
+        write_rate(&mut game.view.views, 0.0);
+        game.write_time();
+        if let Some(target) = game.options.target {
+            write_target(&mut game.view.views, target);
+        }
+        if game.options.lives.is_some() {
+            let lives = game.lives;
+            write_lives(&mut game.view.views, lives);
+        }
+        if game.ghost.is_some() {
+            write_ghost(&mut game.view.views, 0, 0);
+        }
+        if let Some(date) = game.options.daily {
+            write_info(&mut game.view.views, DAILY_ROW, format!("Daily: {}", date));
+        }
+        write_info(
+            &mut game.view.views,
+            HELP_ROW,
+            format!("{}: {}", keys.quit, "quit the game"),
+        );
+        let help = if single_key {
+            ["space: whack", ""]
+        } else {
+            ["asd: whack a row", "zxc: whack a column"]
+        };
+        for (row, line) in (HELP_ROW + 1..).zip(help) {
+            write_info(&mut game.view.views, row, line.to_string());
+        }
+        write_info(
+            &mut game.view.views,
+            HELP_ROW + 3,
+            format!("{}: pause  {}: help", keys.pause, keys.help),
+        );
+        if game.options.endless {
+            let wave = game.wave;
+            write_wave(&mut game.view.views, wave);
+        }
+        let warm_up = game.options.warm_up;
+        if !warm_up.is_zero() {
+            game.write_pause_banner(warm_up);
+        }
+        // 开场动画要等按键，画的是一份拷贝；上一局的更新线程这时已经看得到新的一局，会自己退出
+        let view = game.view.clone();
+        drop(game);
+        play_intro(&view);
+        // 开场动画不占用预热时间
+        lock_game().start_clock();
     }
 
+    // 出地鼠、倒计时和动画都在这一个固定步长的循环里推进，一帧的几步做完再统一重画
+    // 限制了帧率时每一帧醒来一次，把这段时间的步数补齐，游戏节奏不变
+    thread::spawn(|| {
+        let (mut updater, frame_time) = {
+            let game = lock_game();
+            (
+                Updater::new(&game),
+                (Duration::from_secs(1) / game.options.max_fps).max(UPDATE_STEP),
+            )
+        };
+        let mut last = Instant::now();
+        let mut behind = Duration::ZERO;
+        loop {
+            // 睡眠时不持有锁，否则按键要等到这一帧更新完才能得到处理
+            assert_game_unlocked();
+            std::thread::sleep(frame_time.saturating_sub(behind));
+            let now = Instant::now();
+            // 落后太多时丢掉补不上的部分，免得越补越慢
+            behind = (behind + (now - last)).min(frame_time + UPDATE_STEP * MAX_CATCH_UP);
+            last = now;
+            let mut game = lock_game();
+            if game.is_over(updater.generation) {
+                log_event!(
+                    "slowest update {}us of {}us",
+                    updater.slowest.as_micros(),
+                    UPDATE_STEP.as_micros()
+                );
+                return;
+            }
+            let mut frame = Frame::default();
+            while behind >= UPDATE_STEP && !game.is_over(updater.generation) {
+                behind -= UPDATE_STEP;
+                updater.step(&mut game, &mut frame);
+            }
+            if !game.help_open {
+                frame.draw(&game.view);
+            }
+        }
+    });
+}
+
+// 开局之后一直处理输入，直到玩家退出
+fn run() -> Result<(), GameError> {
     clear_terminal();
     start();
     let mut has_egg = false;
+    // 彩蛋横幅显示期间本局已经结束、不再出地鼠，按任意键回到结算画面
+    let mut egg_banner = false;
+    let (keys, single_key, key_repeat, points, kiosk, hit_radius) = {
+        let game = lock_game();
+        (
+            game.options.keys,
+            game.options.single_key,
+            game.options.key_repeat,
+            game.options.points_per_hit,
+            game.options.kiosk.is_some(),
+            game.options.hit_radius,
+        )
+    };
+    // 因为窗口失去焦点而自动暂停的，回到窗口时才自动继续；玩家自己暂停的不动
+    let mut focus_paused = false;
+    let mut help_paused = false;
     loop {
-        let event = read()?;
+        // 等按键时不持有锁，更新线程照常推进
+        assert_game_unlocked();
+        // 展示模式下没人按键也要定时醒来，看看是不是该重开了
+        if kiosk && !poll(KIOSK_POLL).map_err(GameError::Input)? {
+            let mut game = lock_game();
+            if game.kiosk_due() {
+                log_event!("kiosk restart");
+                egg_banner = false;
+                game.restart();
+                drop(game);
+                start();
+            }
+            continue;
+        }
+        let event = read().map_err(GameError::Input)?;
+        match event {
+            Event::FocusLost => {
+                let mut game = lock_game();
+                if game.state != GameState::Stopped && !game.paused {
+                    log_event!("focus lost");
+                    game.toggle_pause();
+                    game.view.draw();
+                    focus_paused = true;
+                }
+            }
+            // 单键模式下没有具体的洞可点，彩蛋横幅显示时也不算
+            Event::Mouse(MouseEvent {
+                kind: MouseEventKind::Down(MouseButton::Left),
+                column,
+                row,
+                ..
+            }) if !single_key && !egg_banner => {
+                let mut game = lock_game();
+                if game.help_open {
+                    continue;
+                }
+                let idx = game
+                    .view
+                    .view_column(column.into())
+                    .and_then(|x| game.view.hole_at(x, row.into(), hit_radius));
+                if let Some(idx) = idx {
+                    game.whack_holes(&[idx], points);
+                }
+            }
+            Event::FocusGained => {
+                let mut game = lock_game();
+                if focus_paused && game.paused {
+                    log_event!("focus gained");
+                    game.toggle_pause();
+                    game.view.draw();
+                }
+                focus_paused = false;
+            }
+            _ => {}
+        }
         if let Event::Key(key_event) = event {
-            match key_event.code {
-                KeyCode::Char(ch) => match ch {
-                    'q' => break,
-                    '1'..='9'  => {
-                        let mut game = GAME.lock().unwrap();
-                        let marmots = &game.view.hole_marmots;
-                        let points = &game.view.hole_points;
-                        let idx = ch.to_digit(10).unwrap() as usize - 1;
-                        let point_x = points[idx].x;
-                        let point_y = points[idx].y;
-                        drop(points);
-                        if marmots[idx].appeared {
-                            write_words(
-                                &mut game.view.views,
-                                point_x,
-                                point_y,
-                                String::from("❌"),
-                            );
+            let ignored = match key_event.kind {
+                KeyEventKind::Press => false,
+                KeyEventKind::Repeat => key_repeat == KeyRepeat::Ignore,
+                KeyEventKind::Release => true,
+            };
+            if ignored {
+                continue;
+            }
+            // raw 模式下 Ctrl-C 不会变成信号，而是一个按键，和退出键一样收尾
+            if key_event.modifiers.contains(KeyModifiers::CONTROL)
+                && key_event.code == KeyCode::Char('c')
+            {
+                log_event!("quit: ctrl-c");
+                lock_game().end_game(EndReason::Quit);
+                break;
+            }
+            let settling = lock_game().settling();
+            if settling {
+                log_event!("key ignored: the game just ended");
+                continue;
+            }
+            // 横幅占满整屏，画面盖不住的地方会留下横幅的字，先整屏擦掉再画
+            if egg_banner {
+                egg_banner = false;
+                let game = lock_game();
+                let mut out = output();
+                let _ = queue!(out, Clear(ClearType::All));
+                let _ = game.view.draw_to(&mut *out);
+                continue;
+            }
+            // 说明框开着时任意键都只是关掉它，打开时是它暂停的就接着玩
+            {
+                let mut game = lock_game();
+                if game.help_open {
+                    game.help_open = false;
+                    if help_paused {
+                        help_paused = false;
+                        game.toggle_pause();
+                    }
+                    game.view.draw();
+                    continue;
+                }
+            }
+            // 回车和 r 一样，按这一局的设置马上再来一局
+            let code = match key_event.code {
+                KeyCode::Enter => KeyCode::Char('r'),
+                code => code,
+            };
+            if let KeyCode::Char(ch) = code {
+                match ch {
+                    _ if ch == keys.quit => {
+                        log_event!("quit");
+                        lock_game().end_game(EndReason::Quit);
+                        break;
+                    }
+                    _ if ch == keys.pause => {
+                        let mut game = lock_game();
+                        game.toggle_pause();
+                        game.view.draw();
+                    }
+                    _ if ch == keys.reveal => {
+                        let mut game = lock_game();
+                        game.toggle_reveal();
+                        game.view.draw();
+                    }
+                    _ if ch == keys.help || ch == 'h' => {
+                        let mut game = lock_game();
+                        // 紧凑模式只有一行，画不出说明框，打开了只会停在看不见的说明后面
+                        if game.options.compact {
+                            log_event!("help ignored in compact mode");
+                            continue;
+                        }
+                        help_paused = game.state != GameState::Stopped && !game.paused;
+                        if help_paused {
+                            game.toggle_pause();
+                        }
+                        game.help_open = true;
+                        let lines = help_lines(&game.options);
+                        game.view.with_overlay(&lines).draw();
+                    }
+                    _ if ch == keys.mute => {
+                        let mut game = lock_game();
+                        game.toggle_sound();
+                        game.view.draw_rows(&[STATUS_ROW]);
+                    }
+                    _ if ch == keys.hud => {
+                        let mut game = lock_game();
+                        game.cycle_hud();
+                        game.view.draw();
+                    }
+                    'r' | 'e' => {
+                        let mut game = lock_game();
+                        if game.state != GameState::Stopped {
+                            continue;
+                        }
+                        if ch == 'r' {
+                            game.restart();
+                            drop(game);
+                            start();
+                        } else {
+                            game.reset_session();
                             game.view.draw();
-                            game.scores += 10;
                         }
                     }
-                    _ => (),
-                },
-                _ => {}
+                    ' ' if single_key => {
+                        let mut game = lock_game();
+                        let idx = game.view.holes.iter().position(|hole| hole.marmot.appeared);
+                        let dropped = game.dropped_hits();
+                        match idx {
+                            Some(idx) if game.whack(idx, points) => game.after_hits(&[idx]),
+                            _ if game.dropped_hits() > dropped => {}
+                            _ => {
+                                if game.on_miss(None) {
+                                    game.view.draw();
+                                } else {
+                                    game.view.draw_rows(&[COMBO_ROW, STATS_ROW, STATS_ROW + 1]);
+                                }
+                            }
+                        }
+                    }
+                    '1'..='9' => {
+                        let mut game = lock_game();
+                        let digit = ch.to_digit(10).unwrap() as usize - 1;
+                        game.whack_key(digit, points);
+                    }
+                    _ => lock_game().whack_sweep(ch, points),
+                }
             }
         }
 
-        {
-            let mut game = GAME.lock().unwrap();
-            if game.scores > 1024 && !has_egg {
-                game.state = GameState::Stopped;
+        if !has_egg {
+            let reached = lock_game().reach_milestone();
+            if reached {
                 has_egg = true;
-                clear_terminal();
-                let _ = execute!(stdout(), Clear(ClearType::All));
-                let mut canvas = Canvas::new(30, 20);
-                canvas.text(
-                    35,
-                    20,
-                    150,
+                egg_banner = true;
+                draw_banner(
                     "1024 cheers! 恭喜你过关啦！！🌈 可以找 chongbayang 拿红包哦~",
+                    "Press any key to continue",
                 );
-                println!("{}", canvas.frame());
             }
         }
     }
-    leave_alternate_screen();
-    disable_raw_mode()?;
-
     Ok(())
 }
+
+// 两步都做，返回离开备用屏幕时的错误
+fn teardown_terminal() -> io::Result<()> {
+    let left = leave_alternate_screen(&mut *output());
+    let _ = disable_raw_mode();
+    left
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use hud::Hud;
+
+    #[test]
+    fn info_panel_is_clipped_on_narrow_views() {
+        let size = Dimension {
+            width: 45,
+            height: 25,
+        };
+        let mut view = GameView::new(&size);
+        view.build_block(0, size.height - 1, 0, size.width - 1)
+            .unwrap();
+        view.build_block(0, size.height - 1, 0, DIVIDER).unwrap();
+        write_scores(&mut view.views, 1024);
+        write_info(&mut view.views, HELP_ROW, format!("q: {}", "quit the game"));
+
+        let frame = view.render_to_string();
+        assert!(frame.lines().all(|line| line.chars().count() == size.width));
+        assert!(frame.lines().nth(SCORE_ROW).unwrap().ends_with(" Sc║"));
+        assert_eq!(info_left(size.width), DIVIDER + 2);
+    }
+
+    fn built_view(width: usize, height: usize, blocks: &[[usize; 4]]) -> GameView {
+        let mut view = GameView::new(&Dimension { width, height });
+        for &[top, bottom, left, right] in blocks {
+            view.build_block(top, bottom, left, right).unwrap();
+        }
+        view
+    }
+
+    // 默认布局的九个洞，不画边框
+    fn grid_view() -> GameView {
+        let mut view = GameView::new(&BOARD_SIZE);
+        for hole in &Layout::grid(layout::DEFAULT_GAP).unwrap().holes {
+            view.add_hole(hole);
+        }
+        view
+    }
+
+    #[test]
+    fn compact_line_shows_the_holes_and_the_info_panel() {
+        let mut view = grid_view();
+        view.compact = true;
+        view.color = false;
+        view.spawn(&[1]);
+        write_scores(&mut view.views, 30);
+        write_info(&mut view.views, TIME_ROW, String::from("Time: 12"));
+        let line = view.compact_line();
+        assert_eq!(line.width(), COMPACT_SIZE.width);
+        let map = view.glyphs.map[1];
+        let mole = view.glyphs.mole;
+        let expected = format!("{0}  {1} {0}  {0}  ", map, mole);
+        assert!(line.starts_with(&expected), "{:?}", line);
+        assert!(
+            line.trim_end().ends_with("  Scores: 30  Time: 12"),
+            "{:?}",
+            line
+        );
+    }
+
+    #[test]
+    fn first_spawn_always_uses_the_clearest_hole() {
+        let mut view = grid_view();
+        for seed in 0..20 {
+            let mut rng = StdRng::seed_from_u64(seed);
+            assert_eq!(view.plan_first_spawn(&mut rng, 1), [4]);
+            let holes = view.plan_first_spawn(&mut rng, 3);
+            assert_eq!(holes[0], 4);
+            assert!(holes.len() <= 3 && !holes[1..].contains(&4));
+        }
+        // 中间的洞不出地鼠时换成剩下的洞里权重最大、编号最小的
+        view.holes[4].active = false;
+        assert_eq!(view.clearest_hole(), Some(1));
+    }
+
+    #[test]
+    fn hud_levels_hide_panel_rows_only_during_play() {
+        *output() = Box::new(io::sink());
+        let mut game = Game::new(&BOARD_SIZE);
+        game.view = built_view(70, 25, &[[0, 24, 0, 69], [0, 24, 0, DIVIDER]]);
+        game.options.hud = Hud::Minimal;
+        game.restart();
+        game.write_scores();
+        game.on_miss(None);
+        let row = |game: &Game, y: usize| game.view.render_row(y);
+        assert!(row(&game, SCORE_ROW).contains("Scores: 0"));
+        assert!(!row(&game, COMBO_ROW).contains("Combo"));
+        assert!(!row(&game, STATS_ROW).contains("Misses"));
+        assert!(row(&game, COMBO_ROW).ends_with('║'));
+        game.cycle_hud();
+        assert!(row(&game, COMBO_ROW).contains("Combo"));
+        assert!(!row(&game, STATS_ROW).contains("Misses"));
+        game.cycle_hud();
+        assert!(row(&game, STATS_ROW).contains("Hits: 0  Misses: 1"));
+        game.cycle_hud();
+        game.end_game(EndReason::Quit);
+        assert!(game.view.hidden_rows.is_empty());
+        assert_eq!(game.options.hud, Hud::Minimal);
+    }
+
+    // 发布构建里没有断言，真的会死锁
+    #[cfg(debug_assertions)]
+    #[test]
+    #[should_panic(expected = "already locked by this thread")]
+    fn locking_the_game_twice_on_one_thread_panics() {
+        drop(lock_game());
+        assert_game_unlocked();
+        let _game = lock_game();
+        let _again = lock_game();
+    }
+
+    #[test]
+    fn keys_are_ignored_right_after_the_game_ends() {
+        *output() = Box::new(io::sink());
+        let mut game = Game::new(&BOARD_SIZE);
+        game.view = grid_view();
+        game.options.end_delay = Duration::from_secs(60);
+        game.restart();
+        assert!(!game.settling());
+        game.end_game(EndReason::Timeout);
+        assert!(game.settling());
+        game.ended_at = Some(Instant::now() - Duration::from_secs(61));
+        assert!(!game.settling());
+        game.options.end_delay = Duration::ZERO;
+        game.restart();
+        game.end_game(EndReason::Quit);
+        assert!(!game.settling());
+    }
+
+    #[test]
+    fn score_rate_stops_falling_while_paused() {
+        *output() = Box::new(io::sink());
+        let mut game = Game::new(&BOARD_SIZE);
+        game.view = grid_view();
+        game.restart();
+        let now = Instant::now();
+        game.started_at = now - Duration::from_secs(20);
+        game.paused_for = Duration::from_secs(5);
+        game.scores = 100;
+        game.toggle_pause();
+        game.paused_at = Some(now - Duration::from_secs(5));
+        assert!((game.score_rate() - 10.0).abs() < 0.1);
+        game.toggle_pause();
+        assert_eq!(game.paused_at, None);
+        assert!(game.paused_for >= Duration::from_secs(10));
+        assert!((game.score_rate() - 10.0).abs() < 0.1);
+    }
+
+    #[test]
+    fn pausing_does_not_use_up_the_warm_up() {
+        *output() = Box::new(io::sink());
+        let mut game = Game::new(&BOARD_SIZE);
+        game.view = grid_view();
+        game.options.warm_up = Duration::from_secs(3);
+        game.restart();
+        game.toggle_pause();
+        game.paused_at = Some(Instant::now() - Duration::from_secs(10));
+        game.toggle_pause();
+        let left = game.spawn_not_before - Instant::now();
+        assert!(left > Duration::from_millis(2900), "{:?}", left);
+        // 预热已经过完时暂停不会再多出一段预热
+        game.spawn_not_before = Instant::now() - Duration::from_secs(20);
+        game.toggle_pause();
+        game.paused_at = Some(Instant::now() - Duration::from_secs(10));
+        game.toggle_pause();
+        assert!(game.spawn_not_before < Instant::now());
+    }
+
+    #[test]
+    fn endless_games_play_on_past_the_milestone() {
+        *output() = Box::new(io::sink());
+        let mut game = Game::new(&BOARD_SIZE);
+        game.view = grid_view();
+        game.options.endless = true;
+        game.restart();
+        game.scores = 1025;
+        assert!(!game.reach_milestone());
+        assert_eq!(game.state, GameState::Playing);
+        game.options.endless = false;
+        assert!(game.reach_milestone());
+        assert_eq!(game.state, GameState::Stopped);
+    }
+
+    #[test]
+    fn clicks_match_the_nearest_hole_or_nothing() {
+        let view = grid_view();
+        // 第一个洞的边框是 3..=11 列、3..=7 行，地鼠在 (7, 5)
+        assert_eq!(view.hole_at(7, 5, None), Some(0));
+        assert_eq!(view.hole_at(3, 3, None), Some(0));
+        assert_eq!(view.hole_at(2, 5, None), None);
+        assert_eq!(view.hole_at(12, 5, None), None);
+        assert_eq!(view.hole_at(30, 17, None), Some(8));
+        assert_eq!(view.hole_at(60, 5, None), None);
+        assert_eq!(view.hole_at(5, 5, Some(1)), None);
+        assert_eq!(view.hole_at(6, 4, Some(1)), Some(0));
+        // 两个洞之间的空隙离左边的近
+        assert_eq!(view.hole_at(12, 5, Some(10)), Some(0));
+    }
+
+    #[test]
+    fn build_game_follows_the_options_without_a_terminal() {
+        let layout = Layout::grid(layout::DEFAULT_GAP).unwrap();
+        let caps = Capabilities {
+            unicode: false,
+            emoji: false,
+            color: false,
+            alt_screen: false,
+        };
+        let mut options = Options::parse(
+            ["--active-holes", "1,5", "--big-score"]
+                .iter()
+                .map(|arg| arg.to_string()),
+        )
+        .unwrap();
+        options.frame = false;
+        let game = build_game(options, &layout, &caps, None).unwrap();
+        assert_eq!(game.view.holes.len(), layout.holes.len());
+        let active: Vec<bool> = game.view.holes.iter().map(|hole| hole.active).collect();
+        assert_eq!(
+            active,
+            [true, false, false, false, true, false, false, false, false]
+        );
+        assert!(!game.options.big_score);
+        assert!(!game.view.color);
+        assert_eq!(game.state, GameState::Playing);
+    }
+
+    #[test]
+    fn whacks_over_the_hit_limit_are_ignored() {
+        *output() = Box::new(io::sink());
+        let mut game = Game::new(&BOARD_SIZE);
+        game.view = grid_view();
+        game.options.max_hits_per_second = Some(3);
+        game.restart();
+        game.view.spawn(&[0, 1, 2, 3, 4]);
+        let points = game.options.points_per_hit;
+        for digit in 0..5 {
+            game.whack_key(digit, points);
+        }
+        assert_eq!(game.hits, 3);
+        assert_eq!(game.misses, 0);
+        assert_eq!(game.dropped_hits(), 2);
+        let left: Vec<bool> = game.view.holes[..5]
+            .iter()
+            .map(|hole| hole.marmot.appeared)
+            .collect();
+        assert_eq!(left, [false, false, false, true, true]);
+        // 空洞照样算打空
+        game.whack_key(8, points);
+        assert_eq!(game.misses, 1);
+    }
+
+    #[test]
+    fn sweep_keys_do_nothing_in_single_key_mode() {
+        *output() = Box::new(io::sink());
+        let mut game = Game::new(&BOARD_SIZE);
+        game.view = grid_view();
+        game.options.single_key = true;
+        game.restart();
+        game.view.spawn(&[1]);
+        game.whack_sweep('a', 10);
+        assert_eq!((game.scores, game.hits, game.misses), (0, 0, 0));
+        assert!(game.view.holes[1].marmot.appeared);
+        game.options.single_key = false;
+        game.whack_sweep('a', 10);
+        assert_eq!((game.scores, game.hits), (5, 1));
+    }
+
+    #[test]
+    fn single_key_mode_keeps_one_mole_whatever_the_clear_strategy() {
+        *output() = Box::new(io::sink());
+        let mut game = Game::new(&BOARD_SIZE);
+        game.view = grid_view();
+        game.options.single_key = true;
+        game.options.clear = ClearStrategy::Never;
+        for idx in 0..3 {
+            let clear = game.clear_strategy();
+            game.view.spawn_with(&[idx], clear, 0);
+        }
+        let live = game.view.holes.iter().filter(|hole| hole.marmot.appeared);
+        assert_eq!(live.count(), 1);
+        game.options.single_key = false;
+        assert_eq!(game.clear_strategy(), ClearStrategy::Never);
+    }
+
+    #[test]
+    fn one_key_whacks_every_bound_hole_with_a_mole() {
+        *output() = Box::new(io::sink());
+        let mut game = Game::new(&BOARD_SIZE);
+        game.view = grid_view();
+        game.options.hole_keys = vec![(0, vec![0, 4, 8])];
+        game.shuffle_keys();
+        assert_eq!(game.key_map[1], vec![1]);
+        game.state = GameState::Playing;
+        game.view.holes[0].marmot.appeared = true;
+        game.view.holes[8].marmot.appeared = true;
+        game.whack_key(0, 10);
+        assert_eq!((game.scores, game.hits, game.misses), (20, 2, 0));
+        assert_eq!((game.view.holes[0].hits, game.view.holes[8].hits), (1, 1));
+        // 绑定的洞都空着时只算打空一次
+        game.whack_key(0, 10);
+        assert_eq!((game.scores, game.hits, game.misses), (20, 2, 1));
+    }
+
+    #[test]
+    fn a_whack_right_after_a_spawn_scores_before_the_next_step() {
+        *output() = Box::new(io::sink());
+        let mut game = Game::new(&BOARD_SIZE);
+        game.view = grid_view();
+        game.options.warm_up = Duration::ZERO;
+        game.rng = StdRng::seed_from_u64(7);
+        game.restart();
+        let mut updater = Updater::new(&game);
+        let mut frame = Frame::default();
+        let idx = (0..600)
+            .find_map(|_| {
+                updater.step(&mut game, &mut frame);
+                game.view.holes.iter().position(|hole| hole.marmot.appeared)
+            })
+            .expect("no mole spawned within ten seconds");
+        // 不再推进更新循环，也不等这一帧画出来
+        game.whack_holes(&[idx], HIT_POINTS);
+        assert_eq!((game.scores, game.hits), (HIT_POINTS, 1));
+        assert!(!game.view.holes[idx].marmot.appeared);
+    }
+
+    #[test]
+    fn rows_can_be_drawn_into_a_buffer() {
+        let mut view = built_view(4, 3, &[[0, 2, 0, 3]]);
+        view.color = false;
+        let mut out = vec![];
+        view.draw_rows_to(&mut out, &[1]).unwrap();
+        assert_eq!(String::from_utf8(out).unwrap(), "\x1b[2;1H║  ║");
+        let mut out = vec![];
+        view.draw_to(&mut out).unwrap();
+        let frame = String::from_utf8(out).unwrap();
+        assert!(frame.starts_with("\x1b[1;1H╔══╗\x1b[1E║  ║"));
+    }
+
+    #[test]
+    fn zero_terminal_sizes_are_not_trusted() {
+        assert!(plausible_size(Ok((0, 0))).is_none());
+        assert!(plausible_size(Ok((100, 0))).is_none());
+        assert!(plausible_size(Err(io::Error::other("no tty"))).is_none());
+        let size = plausible_size(Ok((100, 30))).unwrap();
+        assert_eq!((size.width, size.height), (100, 30));
+    }
+
+    fn lines(view: &GameView) -> Vec<String> {
+        view.views.iter().map(|row| row.iter().collect()).collect()
+    }
+
+    #[test]
+    fn a_single_block_gets_corners_and_edges() {
+        let view = built_view(4, 3, &[[0, 2, 0, 3]]);
+        assert_eq!(view.points, [[6, 12, 12, 10], [3, 0, 0, 3], [5, 12, 12, 9]]);
+        assert_eq!(lines(&view), ["╔══╗", "║  ║", "╚══╝"]);
+    }
+
+    #[test]
+    fn a_shared_border_becomes_a_tee() {
+        // 和游戏里外框加分隔线的画法一样
+        let view = built_view(5, 3, &[[0, 2, 0, 4], [0, 2, 0, 2]]);
+        assert_eq!(view.points[0], [6, 12, 14, 12, 10]);
+        assert_eq!(view.points[2], [5, 12, 13, 12, 9]);
+        assert_eq!(lines(&view), ["╔═╦═╗", "║ ║ ║", "╚═╩═╝"]);
+    }
+
+    #[test]
+    fn four_blocks_meet_in_a_cross() {
+        let view = built_view(
+            5,
+            5,
+            &[[0, 2, 0, 2], [0, 2, 2, 4], [2, 4, 0, 2], [2, 4, 2, 4]],
+        );
+        assert_eq!(view.points[2], [7, 12, 15, 12, 11]);
+        assert_eq!(lines(&view), ["╔═╦═╗", "║ ║ ║", "╠═╬═╣", "║ ║ ║", "╚═╩═╝"]);
+    }
+
+    #[test]
+    fn blocks_outside_the_view_are_rejected() {
+        let mut view = GameView::new(&BOARD_SIZE);
+        let err = view
+            .build_block(0, BOARD_SIZE.height, 0, DIVIDER)
+            .unwrap_err();
+        assert!(
+            matches!(err, GameError::InvalidDimension { bottom, .. } if bottom == BOARD_SIZE.height)
+        );
+        assert_eq!(err.exit_code(), 1);
+    }
+
+    #[test]
+    fn info_panel_keeps_its_column_on_the_default_view() {
+        assert_eq!(info_left(70), DIVIDER + INFO_GAP);
+    }
+
+    #[test]
+    fn write_words_ignores_out_of_bounds_text() {
+        let mut views = vec![vec![' '; 4]; 2];
+        write_words(&mut views, 2, 1, String::from("abc"));
+        write_words(&mut views, 0, 5, String::from("abc"));
+        assert_eq!(views[1], vec![' ', ' ', 'a', 'b']);
+        assert_eq!(views[0], vec![' '; 4]);
+    }
+
+    #[test]
+    fn clearing_a_wide_mole_erases_both_cells() {
+        let mut views = vec![vec![' '; 4]];
+        write_words(&mut views, 1, 0, pad_hole_glyph("🐭"));
+        assert_eq!(views[0], vec![' ', '🐭', WIDE_TAIL, ' ']);
+        write_words(&mut views, 1, 0, pad_hole_glyph(" "));
+        assert_eq!(views[0], vec![' '; 4]);
+        write_words(&mut views, 1, 0, pad_hole_glyph("🐭"));
+        write_words(&mut views, 1, 0, String::from("██"));
+        assert_eq!(views[0], vec![' ', '█', '█', ' ']);
+    }
+}
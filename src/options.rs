@@ -0,0 +1,656 @@
+use std::{path::PathBuf, str::FromStr, time::Duration};
+use unicode_width::UnicodeWidthStr;
+
+use crate::{
+    caps::BorderStyle,
+    daily::Date,
+    difficulty::{self, ClearStrategy, Difficulty, SpawnCurve, DEFAULT_DIFFICULTY},
+    hud::Hud,
+    keys::{KeyBindings, KeyRepeat},
+    lang::Lang,
+    layout::{Gap, DEFAULT_GAP},
+    theme::{self, Theme, DEFAULT_THEME},
+    HIT_POINTS, HOLE_WIDTH, MAX_FPS,
+};
+
+// 倒计时在信息栏上怎么刷新，实际计时不受影响
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum TimerDisplay {
+    Every(u8), // 每隔这么多秒刷新一次，1 就是每秒都刷新
+    Final,     // 平时不显示，到最后的倒计时才显示
+}
+
+impl FromStr for TimerDisplay {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "final" => Ok(TimerDisplay::Final),
+            _ => match s.parse() {
+                Ok(step) if step >= 1 => Ok(TimerDisplay::Every(step)),
+                _ => Err(format!(
+                    "Invalid value for --timer: {} (expected seconds or final)",
+                    s
+                )),
+            },
+        }
+    }
+}
+
+// 时长类参数的上限，再大会让计时相加溢出
+const MAX_DURATION: Duration = Duration::from_secs(60 * 60);
+// 开局分数和每只得分的上限，保证一局下来总分不会溢出
+const MAX_POINTS: u128 = 1_000_000_000_000_000_000;
+
+// 每只地鼠至少露头这么久，出地鼠再快也只是更密，不会快到打不着
+const MIN_VISIBLE: Duration = Duration::from_millis(600);
+
+// 展示模式下结算画面默认停留的时间
+const KIOSK_DELAY: Duration = Duration::from_secs(5);
+
+// 一局刚结束时默认不理会按键的时长
+const END_DELAY: Duration = Duration::from_millis(500);
+
+#[derive(Clone, Debug)]
+pub struct Options {
+    pub warm_up: Duration,           // 开局后多久才开始出地鼠
+    pub log: Option<PathBuf>,        // 游戏事件日志，调试用
+    pub layout: Option<PathBuf>,     // 自定义棋盘布局文件
+    pub gap: Gap,                    // 默认布局里洞与洞之间的间距，自定义布局时不能用
+    pub border: Option<BorderStyle>, // None 时按终端能力决定
+    pub frame: bool,                 // 画棋盘和洞的边框，关掉时洞上方显示数字键
+    pub debug_layout: bool,          // 在每个洞上标出编号和坐标，调试布局用
+    pub mini_map: bool,              // 在信息栏用一行小方块显示每个洞有没有地鼠
+    pub best_times: Option<PathBuf>, // 保存每个洞历来最快反应时间的文件，结算时标在洞下
+    pub heatmap: bool,               // 结算时在每个洞上显示本局的命中率
+    pub mirror: bool,                // 信息栏放左边，棋盘左右翻转，方便左手玩家
+    pub beat: bool,                  // 在信息栏显示出地鼠的节拍
+    pub target: Option<u128>,        // 目标分数，达到即结束
+    pub overtime: bool,              // 时间到时离目标分数只差一点就进入加时
+    pub tell: Duration,              // 地鼠出现前多久在洞口给出提示，0 表示不提示
+    pub theme: Theme,
+    pub lang: Option<Lang>,    // None 时按 locale 决定
+    pub title: Option<String>, // 终端标题，None 时用界面语言对应的标题
+    pub difficulty: Difficulty,
+    pub curve: SpawnCurve,
+    pub min_visible: Duration, // 地鼠至少待这么久才会被下一轮撤掉，0 表示不保证
+    pub clear: ClearStrategy,  // 每轮出地鼠之前撤掉哪些地鼠，默认只撤待满轮数的，不再一起缩回去
+    pub endless: bool,         // 无尽模式，一波接一波直到主动退出
+    pub kiosk: Option<Duration>, // 展示模式：结束后停留这么久自动开下一局，None 表示不自动开
+    pub end_delay: Duration,   // 一局结束后这么久内的按键都不算，0 表示马上就能按
+    pub empty_glyph: Option<String>, // 空洞显示的字符，最多占洞口宽度，None 时按主题决定
+    pub highlight: bool,       // 有地鼠的洞换个边框颜色，一眼就能看出该打哪里
+    pub heat: bool,            // 连击越高边框颜色越暖，单色终端可以关掉
+    pub unicode: Option<bool>, // 以下三项为 None 时按探测结果决定
+    pub color: Option<bool>,
+    pub alt_screen: Option<bool>,
+    pub start_score: u128,    // 调试用：开局时的分数，用来直接测试 1024 彩蛋
+    pub points_per_hit: u128, // 每打中一只的得分，整行整列打中的减半
+    pub decay: u128,          // 太久没打中时每秒扣的分，0 表示不扣
+    pub lives: Option<u32>,   // 开局有几条命，每溜走一只地鼠少一条，None 表示不限
+    pub cooldown: Duration,   // 按过一个洞后这么久内再按它会被忽略，0 表示不限制
+    pub max_hits_per_second: Option<usize>, // 每秒最多打中几只，超出的不算，None 表示不限
+    pub speed_points: Vec<(Duration, u128)>, // 出现后这么久以内打中得这么多分，按时间从短到长，空的时候一律按每只得分
+    pub late_points: f64,                    // 宽限期内打中所得分数的比例
+    pub grace: Duration,                     // 地鼠缩回去之后这么久内打中仍然算数，0 表示不宽限
+    pub big_score: bool,                     // 用大号数字在信息栏顶部显示得分和时间，方便直播
+    pub achievement_time: Duration,          // 成就横幅显示多久，0 表示不显示
+    pub count_up: bool,                      // 加分时分数滚动上去，而不是直接跳到新的值
+    pub cheats: bool,                        // 打开调试用的作弊键
+    pub timer: TimerDisplay,
+    pub hud: Hud,                            // 对局中信息栏显示多少内容，局中按键切换
+    pub flash: bool,                         // 变色的倒计时是否闪烁
+    pub warn_time: u64,                      // 剩余时间不足这么多秒时倒计时变色，0 表示不提醒
+    pub single_key: bool,                    // 一次只出一只地鼠，按空格就能打中它
+    pub sequence: bool,                      // 顺序模式：地鼠带编号，必须从小到大依次打
+    pub shuffle_keys: bool,                  // 每局打乱数字键和洞的对应关系
+    pub seed: Option<u64>,                   // 随机数种子，相同种子出地鼠的顺序相同
+    pub daily: Option<Date>,                 // 每日挑战：按当天日期定种子
+    pub max_moles: Option<usize>,            // 每轮最多出几只，None 时按难度和洞数算
+    pub active_holes: Option<Vec<usize>>,    // 专项练习：只有这几个洞出地鼠，从 0 开始数
+    pub hole_keys: Vec<(usize, Vec<usize>)>, // 把数字键改绑到一个或几个洞上，都从 0 开始数
+    pub max_fps: u32,                        // 每秒最多重画几次，调低能省电，游戏节奏不受影响
+    pub ghost: bool,                         // 暗色显示本次运行里得分最高那一局同一时刻的地鼠和得分
+    pub zen: bool,                           // 空洞里播放待机动画，纯装饰
+    pub squish: bool,                        // 打中后播放压扁动画，关掉时一直显示打中标记直到下一轮
+    pub escapes: bool,                       // 地鼠没被打中就缩回去时在洞口留个标记
+    pub first_mole: bool,                    // 每局第一轮一定有一只出在最显眼的洞里
+    pub sound: bool,                         // 地鼠溜走时终端响铃
+    pub mouse: bool,                         // 用鼠标点洞
+    pub compact: bool,                       // 整局只占一行，小终端上或者嵌在别的界面里用
+    pub hit_radius: Option<usize>, // 点在离洞口这么多格以内就算点中，None 时要点在洞的边框里
+    pub keys: KeyBindings,
+    pub key_repeat: KeyRepeat,
+    pub list_themes: bool,       // 只列出可用主题，不进入游戏
+    pub list_difficulties: bool, // 只列出可用难度，不进入游戏
+}
+
+impl Default for Options {
+    fn default() -> Self {
+        Options {
+            warm_up: Duration::from_secs(3),
+            log: None,
+            layout: None,
+            gap: DEFAULT_GAP,
+            border: None,
+            frame: true,
+            debug_layout: false,
+            mini_map: false,
+            best_times: None,
+            heatmap: false,
+            mirror: false,
+            beat: false,
+            target: None,
+            overtime: false,
+            tell: Duration::ZERO,
+            theme: theme::find(DEFAULT_THEME).unwrap(),
+            lang: None,
+            title: None,
+            difficulty: difficulty::find(DEFAULT_DIFFICULTY).unwrap(),
+            curve: SpawnCurve::Flat,
+            min_visible: MIN_VISIBLE,
+            clear: ClearStrategy::Expired,
+            endless: false,
+            kiosk: None,
+            end_delay: END_DELAY,
+            empty_glyph: None,
+            highlight: false,
+            heat: true,
+            unicode: None,
+            color: None,
+            alt_screen: None,
+            start_score: 0,
+            points_per_hit: HIT_POINTS,
+            decay: 0,
+            lives: None,
+            cooldown: Duration::ZERO,
+            max_hits_per_second: None,
+            speed_points: vec![],
+            late_points: 0.5,
+            grace: Duration::ZERO,
+            big_score: false,
+            achievement_time: Duration::from_millis(1500),
+            count_up: true,
+            cheats: false,
+            timer: TimerDisplay::Every(1),
+            hud: Hud::Standard,
+            flash: true,
+            warn_time: 10,
+            single_key: false,
+            sequence: false,
+            shuffle_keys: false,
+            seed: None,
+            daily: None,
+            max_moles: None,
+            active_holes: None,
+            hole_keys: vec![],
+            max_fps: MAX_FPS,
+            ghost: false,
+            zen: false,
+            squish: true,
+            escapes: true,
+            first_mole: true,
+            sound: false,
+            mouse: false,
+            compact: false,
+            hit_radius: None,
+            keys: KeyBindings::default(),
+            key_repeat: KeyRepeat::Ignore,
+            list_themes: false,
+            list_difficulties: false,
+        }
+    }
+}
+
+impl Options {
+    pub fn parse<I: Iterator<Item = String>>(mut args: I) -> Result<Self, String> {
+        let mut options = Options::default();
+        while let Some(arg) = args.next() {
+            match arg.as_str() {
+                "--warm-up" => {
+                    options.warm_up = next_duration(&mut args, &arg, Duration::from_secs)?;
+                }
+                "--log" => {
+                    options.log = Some(next_value(&mut args, &arg)?);
+                }
+                "--layout" => {
+                    options.layout = Some(next_value(&mut args, &arg)?);
+                }
+                "--beat" => {
+                    options.beat = true;
+                }
+                "--mirror" => {
+                    options.mirror = true;
+                }
+                "--border" => {
+                    let style: String = next_value(&mut args, &arg)?;
+                    options.border = Some(style.parse()?);
+                }
+                "--no-frame" => {
+                    options.frame = false;
+                }
+                "--gap" => {
+                    options.gap = next_value(&mut args, &arg)?;
+                }
+                "--best-times" => {
+                    options.best_times = Some(next_value(&mut args, &arg)?);
+                }
+                "--heatmap" => {
+                    options.heatmap = true;
+                }
+                "--mini-map" => {
+                    options.mini_map = true;
+                }
+                "--debug-layout" => {
+                    options.debug_layout = true;
+                }
+                "--target" => {
+                    options.target = Some(next_value(&mut args, &arg)?);
+                }
+                "--overtime" => {
+                    options.overtime = true;
+                }
+                "--tell" => {
+                    options.tell = next_duration(&mut args, &arg, Duration::from_millis)?;
+                }
+                "--theme" => {
+                    let name: String = next_value(&mut args, &arg)?;
+                    options.theme =
+                        theme::find(&name).ok_or_else(|| format!("Unknown theme: {}", name))?;
+                }
+                "--lang" => {
+                    let lang: String = next_value(&mut args, &arg)?;
+                    options.lang = Some(lang.parse()?);
+                }
+                "--title" => {
+                    options.title = Some(next_value(&mut args, &arg)?);
+                }
+                "--blitz" => {
+                    options.difficulty = difficulty::find(difficulty::BLITZ).unwrap();
+                }
+                "--difficulty" => {
+                    let name: String = next_value(&mut args, &arg)?;
+                    options.difficulty = difficulty::find(&name)
+                        .ok_or_else(|| format!("Unknown difficulty: {}", name))?;
+                }
+                "--clear" => {
+                    let name: String = next_value(&mut args, &arg)?;
+                    options.clear = name.parse()?;
+                }
+                "--min-visible" => {
+                    options.min_visible = next_duration(&mut args, &arg, Duration::from_millis)?;
+                }
+                "--curve" => {
+                    let name: String = next_value(&mut args, &arg)?;
+                    options.curve = name.parse()?;
+                }
+                "--endless" => {
+                    options.endless = true;
+                }
+                "--empty-glyph" => {
+                    let glyph: String = next_value(&mut args, &arg)?;
+                    if glyph.width() > HOLE_WIDTH {
+                        return Err(format!(
+                            "--empty-glyph must be at most {} cells wide",
+                            HOLE_WIDTH
+                        ));
+                    }
+                    options.empty_glyph = Some(glyph);
+                }
+                "--ascii" | "--unicode" => {
+                    options.unicode = Some(arg == "--unicode");
+                }
+                "--no-color" | "--color" => {
+                    options.color = Some(arg == "--color");
+                }
+                "--no-alt-screen" | "--alt-screen" => {
+                    options.alt_screen = Some(arg == "--alt-screen");
+                }
+                "--highlight" => {
+                    options.highlight = true;
+                }
+                "--no-heat" => {
+                    options.heat = false;
+                }
+                "--shuffle-keys" => {
+                    options.shuffle_keys = true;
+                }
+                "--seed" => {
+                    options.seed = Some(next_value(&mut args, &arg)?);
+                }
+                "--daily" => {
+                    options.daily = Some(Date::today());
+                }
+                "--active-holes" => {
+                    let list: String = next_value(&mut args, &arg)?;
+                    options.active_holes = Some(parse_holes(&list, &arg)?);
+                }
+                "--max-moles" => {
+                    let max_moles = next_value(&mut args, &arg)?;
+                    if max_moles == 0 {
+                        return Err(String::from("--max-moles must be at least 1"));
+                    }
+                    options.max_moles = Some(max_moles);
+                }
+                "--lives" => {
+                    let lives = next_value(&mut args, &arg)?;
+                    if lives == 0 {
+                        return Err(String::from("--lives must be at least 1"));
+                    }
+                    options.lives = Some(lives);
+                }
+                "--warn-time" => {
+                    options.warn_time = next_value(&mut args, &arg)?;
+                }
+                "--timer" => {
+                    let display: String = next_value(&mut args, &arg)?;
+                    options.timer = display.parse()?;
+                }
+                "--hud" => {
+                    let level: String = next_value(&mut args, &arg)?;
+                    options.hud = level.parse()?;
+                }
+                "--no-flash" => {
+                    options.flash = false;
+                }
+                "--start-score" => {
+                    options.start_score = next_value(&mut args, &arg)?;
+                    at_most(&arg, options.start_score, MAX_POINTS)?;
+                }
+                "--points-per-hit" => {
+                    options.points_per_hit = next_value(&mut args, &arg)?;
+                    at_most(&arg, options.points_per_hit, MAX_POINTS)?;
+                    if options.points_per_hit == 0 {
+                        return Err(String::from("--points-per-hit must be positive"));
+                    }
+                }
+                "--decay" => {
+                    options.decay = next_value(&mut args, &arg)?;
+                }
+                "--grace" => {
+                    options.grace = next_duration(&mut args, &arg, Duration::from_millis)?;
+                }
+                "--speed-points" => {
+                    let tiers: String = next_value(&mut args, &arg)?;
+                    options.speed_points = parse_speed_points(&tiers)?;
+                }
+                "--late-points" => {
+                    options.late_points = next_value(&mut args, &arg)?;
+                    if !(0.0..=1.0).contains(&options.late_points) {
+                        return Err(String::from("--late-points must be between 0 and 1"));
+                    }
+                }
+                "--achievement-time" => {
+                    options.achievement_time =
+                        next_duration(&mut args, &arg, Duration::from_millis)?;
+                }
+                "--cooldown" => {
+                    options.cooldown = next_duration(&mut args, &arg, Duration::from_millis)?;
+                }
+                "--max-hits-per-second" => {
+                    let max = next_value(&mut args, &arg)?;
+                    if max == 0 {
+                        return Err(String::from("--max-hits-per-second must be at least 1"));
+                    }
+                    options.max_hits_per_second = Some(max);
+                }
+                "--no-count-up" => {
+                    options.count_up = false;
+                }
+                "--big-score" => {
+                    options.big_score = true;
+                }
+                "--cheats" => {
+                    options.cheats = true;
+                }
+                "--sequence" => {
+                    options.sequence = true;
+                }
+                "--single-key" => {
+                    options.single_key = true;
+                }
+                "--no-squish" => {
+                    options.squish = false;
+                }
+                "--no-escapes" => {
+                    options.escapes = false;
+                }
+                "--no-first-mole" => {
+                    options.first_mole = false;
+                }
+                "--sound" => {
+                    options.sound = true;
+                }
+                "--compact" => {
+                    options.compact = true;
+                }
+                "--mouse" => {
+                    options.mouse = true;
+                }
+                "--hit-radius" => {
+                    options.hit_radius = Some(next_value(&mut args, &arg)?);
+                }
+                "--kiosk" => {
+                    options.kiosk.get_or_insert(KIOSK_DELAY);
+                }
+                "--kiosk-delay" => {
+                    options.kiosk = Some(next_duration(&mut args, &arg, Duration::from_secs)?);
+                }
+                "--end-delay" => {
+                    options.end_delay = next_duration(&mut args, &arg, Duration::from_millis)?;
+                }
+                "--max-fps" => {
+                    options.max_fps = next_value(&mut args, &arg)?;
+                    if !(1..=MAX_FPS).contains(&options.max_fps) {
+                        return Err(format!("--max-fps must be between 1 and {}", MAX_FPS));
+                    }
+                }
+                "--ghost" => {
+                    options.ghost = true;
+                }
+                "--zen" => {
+                    options.zen = true;
+                }
+                "--bind-holes" => {
+                    let spec: String = next_value(&mut args, &arg)?;
+                    options.hole_keys.push(parse_hole_binding(&spec)?);
+                }
+                "--key-repeat" => {
+                    let mode: String = next_value(&mut args, &arg)?;
+                    options.key_repeat = mode.parse()?;
+                }
+                "--bind" => {
+                    let spec: String = next_value(&mut args, &arg)?;
+                    options.keys.bind(&spec)?;
+                }
+                "--list-themes" => {
+                    options.list_themes = true;
+                }
+                "--list-difficulties" => {
+                    options.list_difficulties = true;
+                }
+                _ => return Err(format!("Unknown option: {}", arg)),
+            }
+        }
+        options.keys.validate()?;
+        if options.layout.is_some() && options.gap != DEFAULT_GAP {
+            return Err(String::from("--gap only applies to the built-in layout"));
+        }
+        // 两者都把数字写在洞下方那一行
+        if options.best_times.is_some() && options.heatmap {
+            return Err(String::from(
+                "--best-times and --heatmap cannot be used together",
+            ));
+        }
+        // 精简模式下屏幕上没有棋盘可点
+        if options.compact && options.mouse {
+            return Err(String::from(
+                "--compact and --mouse cannot be used together",
+            ));
+        }
+        if options.hit_radius.is_some() && !options.mouse {
+            return Err(String::from("--hit-radius only applies with --mouse"));
+        }
+        if let Some(date) = options.daily {
+            if options.seed.is_some() {
+                return Err(String::from("--daily and --seed cannot be used together"));
+            }
+            options.seed = Some(date.seed());
+        }
+        if options.tell >= options.difficulty.spawn_interval {
+            return Err(format!(
+                "--tell must be shorter than {}ms",
+                options.difficulty.spawn_interval.as_millis()
+            ));
+        }
+        Ok(options)
+    }
+}
+
+// 逗号分隔的洞编号，和数字键一样从 1 开始
+fn parse_holes(list: &str, name: &str) -> Result<Vec<usize>, String> {
+    list.split(',')
+        .map(|field| match field.trim().parse::<usize>() {
+            Ok(number) if number >= 1 => Ok(number - 1),
+            _ => Err(format!("Invalid hole number for {}: {:?}", name, field)),
+        })
+        .collect()
+}
+
+// 形如 200:20,500:15 的分档：200ms 以内打中得 20 分，500ms 以内得 15 分，再慢按每只得分算
+fn parse_speed_points(tiers: &str) -> Result<Vec<(Duration, u128)>, String> {
+    let mut parsed: Vec<(Duration, u128)> = vec![];
+    for tier in tiers.split(',') {
+        let invalid = || {
+            format!(
+                "Invalid tier for --speed-points: {:?} (expected ms:points)",
+                tier
+            )
+        };
+        let (millis, points) = tier.trim().split_once(':').ok_or_else(invalid)?;
+        let within = Duration::from_millis(millis.parse().map_err(|_| invalid())?);
+        let points: u128 = points.parse().map_err(|_| invalid())?;
+        if within > MAX_DURATION || points == 0 {
+            return Err(invalid());
+        }
+        at_most("--speed-points", points, MAX_POINTS)?;
+        if parsed.last().is_some_and(|&(last, _)| last >= within) {
+            return Err(String::from(
+                "--speed-points tiers must go from the shortest time to the longest",
+            ));
+        }
+        parsed.push((within, points));
+    }
+    Ok(parsed)
+}
+
+// 形如 1=1,5 的一条绑定：数字键 1 同时打第 1 和第 5 个洞
+fn parse_hole_binding(spec: &str) -> Result<(usize, Vec<usize>), String> {
+    let invalid = || format!("Invalid hole binding: {} (expected digit=holes)", spec);
+    let (key, holes) = spec.split_once('=').ok_or_else(invalid)?;
+    let digit = match key.parse::<usize>() {
+        Ok(digit @ 1..=9) => digit - 1,
+        _ => return Err(invalid()),
+    };
+    Ok((digit, parse_holes(holes, "--bind-holes")?))
+}
+
+// 时长按 unit 换算，超过上限时报出参数名和原值
+fn next_duration<I: Iterator<Item = String>>(
+    args: &mut I,
+    name: &str,
+    unit: fn(u64) -> Duration,
+) -> Result<Duration, String> {
+    let value = next_value(args, name)?;
+    let duration = unit(value);
+    if duration > MAX_DURATION {
+        return Err(format!(
+            "Value for {} is too large: {} (at most {} seconds)",
+            name,
+            value,
+            MAX_DURATION.as_secs()
+        ));
+    }
+    Ok(duration)
+}
+
+fn at_most(name: &str, value: u128, max: u128) -> Result<(), String> {
+    if value > max {
+        return Err(format!(
+            "Value for {} is too large: {} (at most {})",
+            name, value, max
+        ));
+    }
+    Ok(())
+}
+
+fn next_value<T: FromStr, I: Iterator<Item = String>>(
+    args: &mut I,
+    name: &str,
+) -> Result<T, String> {
+    let value = args
+        .next()
+        .ok_or_else(|| format!("Missing value for {}", name))?;
+    value
+        .parse()
+        .map_err(|_| format!("Invalid value for {}: {}", name, value))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(args: &[&str]) -> Result<Options, String> {
+        Options::parse(args.iter().map(|arg| arg.to_string()))
+    }
+
+    #[test]
+    fn malformed_values_name_the_option_and_value() {
+        let cases: [(&[&str], &str); 6] = [
+            (&["--gap", "-3"], "Invalid value for --gap: -3"),
+            (&["--lives"], "Missing value for --lives"),
+            (
+                &["--warm-up", "99999999999"],
+                "Value for --warm-up is too large: 99999999999 (at most 3600 seconds)",
+            ),
+            (
+                &["--grace", "18446744073709551615"],
+                "Value for --grace is too large: 18446744073709551615 (at most 3600 seconds)",
+            ),
+            (
+                &["--clear", "some"],
+                "Unknown clear strategy: some (expected all, expired or none)",
+            ),
+            (
+                &["--points-per-hit", "340282366920938463463374607431768211455"],
+                "Value for --points-per-hit is too large: 340282366920938463463374607431768211455 (at most 1000000000000000000)",
+            ),
+        ];
+        for (args, message) in cases {
+            assert_eq!(parse(args).unwrap_err(), message, "{:?}", args);
+        }
+    }
+
+    #[test]
+    fn defaults_and_limits_are_accepted() {
+        let options = parse(&[]).unwrap();
+        assert_eq!(options.warm_up, Duration::from_secs(3));
+        let options = parse(&[
+            "--kiosk-delay",
+            "3600",
+            "--cooldown",
+            "0",
+            "--end-delay",
+            "0",
+        ])
+        .unwrap();
+        assert_eq!(options.kiosk, Some(MAX_DURATION));
+        assert_eq!(options.cooldown, Duration::ZERO);
+        assert_eq!(options.end_delay, Duration::ZERO);
+        let options = parse(&["--speed-points", "200:20, 500:15"]).unwrap();
+        let ms = Duration::from_millis;
+        assert_eq!(options.speed_points, [(ms(200), 20), (ms(500), 15)]);
+        assert!(parse(&["--speed-points", "500:15,200:20"]).is_err());
+        assert!(parse(&["--speed-points", "200:0"]).is_err());
+    }
+}
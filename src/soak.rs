@@ -0,0 +1,93 @@
+use std::{
+    hint::black_box,
+    io,
+    panic::{self, AssertUnwindSafe},
+    time::{Duration, Instant},
+};
+
+use rand::{rngs::StdRng, Rng, SeedableRng};
+
+use crate::{
+    error::GameError,
+    layout::{Layout, DEFAULT_GAP},
+    output, Frame, Game, GameState, Updater, BOARD_SIZE, DIVIDER,
+};
+
+// 演示玩家每帧打中一只露头地鼠、随手打空一个洞的概率
+const HIT_CHANCE: f64 = 0.05;
+const MISS_CHANCE: f64 = 0.005;
+
+// 不碰终端也不睡眠，让演示玩家对着固定种子的游戏连跑若干帧，一局结束就接着开下一局
+pub fn run(frames: u64, seed: u64) -> Result<(), GameError> {
+    *output() = Box::new(io::sink());
+    let mut game = board(seed)?;
+    let mut rng = StdRng::seed_from_u64(seed);
+    let mut done = 0;
+    let mut slowest = Duration::ZERO;
+    let started_at = Instant::now();
+    let result = panic::catch_unwind(AssertUnwindSafe(|| {
+        let mut updater = Updater::new(&game);
+        while done < frames {
+            updater.step(&mut game, &mut Frame::default());
+            play(&mut game, &mut rng);
+            black_box(game.view.render_to_string());
+            if game.state == GameState::Stopped {
+                slowest = slowest.max(updater.slowest);
+                game.restart();
+                updater = Updater::new(&game);
+            }
+            done += 1;
+        }
+        slowest = slowest.max(updater.slowest);
+    }));
+    if result.is_err() {
+        return Err(GameError::SoakPanicked { frame: done });
+    }
+    let elapsed = started_at.elapsed();
+    let session = &game.session;
+    println!(
+        "{} frames, {} games, {} hits, {} misses in {}ms ({}us per frame, slowest update {}us)",
+        frames,
+        session.games,
+        session.hits,
+        session.misses,
+        elapsed.as_millis(),
+        elapsed.as_micros() / u128::from(frames.max(1)),
+        slowest.as_micros()
+    );
+    Ok(())
+}
+
+// 默认棋盘，不出地鼠的预热期也省掉
+fn board(seed: u64) -> Result<Game, GameError> {
+    let size = BOARD_SIZE;
+    let mut game = Game::new(&size);
+    game.options.seed = Some(seed);
+    game.options.warm_up = Duration::ZERO;
+    game.rng = StdRng::seed_from_u64(seed);
+    game.view
+        .build_block(0, size.height - 1, 0, size.width - 1)?;
+    game.view.build_block(0, size.height - 1, 0, DIVIDER)?;
+    let layout = Layout::grid(DEFAULT_GAP).map_err(GameError::InvalidLayout)?;
+    for hole in &layout.holes {
+        game.view
+            .build_block(hole.top, hole.bottom, hole.left, hole.right)?;
+        game.view.add_hole(hole);
+    }
+    game.restart();
+    Ok(game)
+}
+
+// 演示玩家：露头的地鼠每帧有一定概率被打中，偶尔手滑打空一个洞
+fn play(game: &mut Game, rng: &mut StdRng) {
+    let points = game.options.points_per_hit;
+    for idx in 0..game.view.holes.len() {
+        if game.view.holes[idx].marmot.appeared && rng.gen_bool(HIT_CHANCE) {
+            game.whack_holes(&[idx], points);
+        }
+    }
+    if rng.gen_bool(MISS_CHANCE) {
+        let idx = rng.gen_range(0..game.view.holes.len());
+        game.whack_holes(&[idx], points);
+    }
+}
@@ -0,0 +1,78 @@
+use crossterm::style::Color;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Theme {
+    pub name: &'static str,
+    pub frame: Color,    // 边框颜色
+    pub text: Color,     // 其余文字颜色
+    pub warning: Color,  // 快没时间时倒计时的颜色
+    pub occupied: Color, // 打开 --highlight 时有地鼠的洞的边框颜色
+    pub glyphs: ThemeGlyphs,
+}
+
+// 主题自己的地鼠、打中标记和空洞字符，None 时用随终端能力变化的默认字符
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct ThemeGlyphs {
+    pub mole: Option<&'static str>,
+    pub hit: Option<&'static str>,
+    pub empty: Option<&'static str>,
+}
+
+const DEFAULT_GLYPHS: ThemeGlyphs = ThemeGlyphs {
+    mole: None,
+    hit: None,
+    empty: None,
+};
+
+pub const DEFAULT_THEME: &str = "classic";
+
+pub const THEMES: [Theme; 3] = [
+    Theme {
+        name: "classic",
+        frame: Color::Reset,
+        text: Color::Reset,
+        warning: Color::Red,
+        occupied: Color::Yellow,
+        glyphs: DEFAULT_GLYPHS,
+    },
+    Theme {
+        name: "forest",
+        frame: Color::DarkGreen,
+        text: Color::Green,
+        warning: Color::DarkYellow,
+        occupied: Color::Yellow,
+        glyphs: ThemeGlyphs {
+            mole: Some("🦔"),
+            hit: Some("🍂"),
+            empty: None,
+        },
+    },
+    Theme {
+        name: "ocean",
+        frame: Color::DarkBlue,
+        text: Color::Cyan,
+        warning: Color::Magenta,
+        occupied: Color::White,
+        glyphs: ThemeGlyphs {
+            mole: Some("🐙"),
+            hit: Some("🌊"),
+            empty: None,
+        },
+    },
+];
+
+// 连击每满这么多下，边框就再暖一档
+const HEAT_STEP: u32 = 5;
+const HEAT_COLORS: [Color; 3] = [Color::Yellow, Color::DarkYellow, Color::Red];
+
+// 连击不够一档时不改颜色，用回主题的边框颜色
+pub fn heat(combo: u32) -> Option<Color> {
+    let level = (combo / HEAT_STEP) as usize;
+    level
+        .checked_sub(1)
+        .map(|level| HEAT_COLORS[level.min(HEAT_COLORS.len() - 1)])
+}
+
+pub fn find(name: &str) -> Option<Theme> {
+    THEMES.iter().copied().find(|t| t.name == name)
+}